@@ -0,0 +1,8 @@
+-- --------------------------------------------------------
+-- SQLite version of `migrations/20250114063231_kv_store.sql`
+-- (SQLite requires column definitions before table constraints)
+CREATE TABLE kv_store (
+    id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    PRIMARY KEY (id)
+);
@@ -0,0 +1,266 @@
+//! Keeps rolling snapshots of all the stored data on disk so that they can be restored.
+//!
+//! Backups are taken periodically and before destructive operations. Only the most recent ones
+//! are kept (see [`SharedConfig::backup_keep`])
+
+use std::{fmt::Display, fs, path::PathBuf, sync::Mutex};
+
+use anyhow::Context as _;
+use poise::serenity_prelude::CacheHttp;
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    SharedConfig,
+    db::FileStore,
+    model::{schedule::UnixTimestamp, snapshot::Snapshot, versioned},
+    user_error::UserError,
+};
+
+const REASON_PERIODIC: &str = "periodic";
+
+/// Held while choosing a name and writing a backup so that two backups cannot pick the same name
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug)]
+pub struct BackupEntry {
+    /// Used to identify the backup for a restore
+    pub name: String,
+    pub created_at: UnixTimestamp,
+    /// What triggered the backup
+    pub reason: String,
+    /// Distinguishes backups created in the same second (starts at 1)
+    sequence: u32,
+    path: PathBuf,
+}
+
+fn backup_folder() -> PathBuf {
+    PathBuf::from(FileStore::DEFAULT_FOLDER).join("backups")
+}
+
+/// Returns the handle of the background task (if backups are enabled) so it can be stopped when
+/// shutting down
+pub fn start_periodic_backups(shared_config: &'static SharedConfig) -> Option<JoinHandle<()>> {
+    let Some(interval) = shared_config.backup_interval else {
+        warn!("Periodic backups are disabled");
+        return None;
+    };
+    Some(tokio::spawn(async move {
+        info!("Periodic backups started");
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(err) = take_backup(shared_config, REASON_PERIODIC).await {
+                error!(?err, "failed to take periodic backup");
+            }
+        }
+    }))
+}
+
+/// Saves a snapshot of all keys to the backup folder and removes the oldest backups over the limit
+#[instrument(skip(shared_config))]
+pub async fn take_backup(
+    shared_config: &SharedConfig,
+    reason: &str,
+) -> anyhow::Result<BackupEntry> {
+    let snapshot = Snapshot::create(shared_config).await?;
+    let content = snapshot.to_json()?;
+    let folder = backup_folder();
+    let result = {
+        let _guard = match WRITE_LOCK.lock() {
+            Ok(guard) => guard,
+            Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+        };
+        fs::create_dir_all(&folder)
+            .with_context(|| format!("failed to create backup folder: {folder:?}"))?;
+        let (name, sequence) = unique_name(snapshot.created_at, reason, |name| {
+            folder.join(format!("{name}.json")).exists()
+        });
+        let path = folder.join(format!("{name}.json"));
+        FileStore::write_atomically(&path, &content)
+            .with_context(|| format!("failed to write backup to {path:?}"))?;
+        info!("Backup saved to {path:?}");
+        prune(shared_config.backup_keep.get())?;
+        BackupEntry {
+            name,
+            created_at: snapshot.created_at,
+            reason: reason.to_string(),
+            sequence,
+            path,
+        }
+    };
+    Ok(result)
+}
+
+/// Takes a backup before a destructive operation. A failure is logged and posted in the bot status
+/// channel but does not stop the operation (eg. a full disk should not skip the scheduled start
+/// of the event)
+pub async fn take_backup_or_report(
+    cache_http: impl CacheHttp,
+    shared_config: &SharedConfig,
+    reason: &str,
+) {
+    let Err(err) = take_backup(shared_config, reason).await else {
+        return;
+    };
+    error!(?err, "failed to take backup before {reason}");
+    match shared_config.channel_bot_status {
+        Some(channel) => {
+            let msg = format!(
+                "⚠️ Failed to take a backup before `{reason}` (continued without one)
+```
+{err:#}
+```"
+            );
+            if let Err(e) = channel.say(&cache_http, msg).await {
+                error!("failed to report failed backup in bot status channel: {e:?}");
+            }
+        }
+        None => warn!("Not reporting failed backup because channel_bot_status not set"),
+    }
+}
+
+/// Returns the first name (and its sequence number) not already taken by another backup
+fn unique_name(
+    created_at: UnixTimestamp,
+    reason: &str,
+    is_taken: impl Fn(&str) -> bool,
+) -> (String, u32) {
+    let mut sequence = 1;
+    loop {
+        let name = if sequence == 1 {
+            format!("{}_{reason}", created_at.0)
+        } else {
+            format!("{}-{sequence}_{reason}", created_at.0)
+        };
+        if !is_taken(&name) {
+            return (name, sequence);
+        }
+        sequence += 1;
+    }
+}
+
+/// Splits a backup name into when it was created, its sequence number and the reason
+fn parse_name(name: &str) -> Option<(UnixTimestamp, u32, &str)> {
+    let (created, reason) = name.split_once('_')?;
+    let (timestamp, sequence) = match created.split_once('-') {
+        Some((timestamp, sequence)) => (timestamp, sequence.parse().ok()?),
+        None => (created, 1),
+    };
+    Some((
+        UnixTimestamp::new(timestamp.parse().ok()?),
+        sequence,
+        reason,
+    ))
+}
+
+/// Returns the backups newest first
+pub fn list() -> anyhow::Result<Vec<BackupEntry>> {
+    let folder = backup_folder();
+    let entries = match fs::read_dir(&folder) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read backup folder: {folder:?}"));
+        }
+    };
+    let mut result = Vec::new();
+    for entry in entries {
+        let path = entry
+            .context("failed to read entry in backup folder")?
+            .path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let Some((created_at, sequence, reason)) = parse_name(name) else {
+            warn!("Skipping unexpected file in backup folder: {path:?}");
+            continue;
+        };
+        result.push(BackupEntry {
+            name: name.to_string(),
+            created_at,
+            reason: reason.to_string(),
+            sequence,
+            path,
+        });
+    }
+    result.sort_by_key(|entry| std::cmp::Reverse((entry.created_at.0, entry.sequence)));
+    Ok(result)
+}
+
+/// Loads the snapshot stored in the backup with this name
+pub fn load(name: &str) -> anyhow::Result<Snapshot> {
+    let entry = list()?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| UserError::new(format!("no backup found named {name:?}")))?;
+    let content = fs::read_to_string(&entry.path)
+        .with_context(|| format!("failed to read backup from {:?}", entry.path))?;
+    versioned::from_json(&content).with_context(|| format!("failed to load backup {name:?}"))
+}
+
+fn prune(keep: usize) -> anyhow::Result<()> {
+    for entry in list()?.into_iter().skip(keep) {
+        info!("Removing old backup {:?}", entry.path);
+        fs::remove_file(&entry.path)
+            .with_context(|| format!("failed to remove old backup {:?}", entry.path))?;
+    }
+    Ok(())
+}
+
+impl Display for BackupEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` ({} at {})",
+            self.name, self.reason, self.created_at
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const CREATED: i64 = 1_893_456_000;
+
+    #[rstest]
+    #[case::first(&[], "1893456000_manual", 1)]
+    #[case::same_second(&["1893456000_manual"], "1893456000-2_manual", 2)]
+    #[case::third(&["1893456000_manual", "1893456000-2_manual"], "1893456000-3_manual", 3)]
+    #[case::other_reason(&["1893456000_periodic"], "1893456000_manual", 1)]
+    fn never_reuses_a_name(
+        #[case] taken: &[&str],
+        #[case] expected_name: &str,
+        #[case] expected_sequence: u32,
+    ) {
+        let (name, sequence) = unique_name(UnixTimestamp::new(CREATED), "manual", |name| {
+            taken.contains(&name)
+        });
+        assert_eq!(name, expected_name);
+        assert_eq!(sequence, expected_sequence);
+        assert_eq!(
+            parse_name(&name),
+            Some((UnixTimestamp::new(CREATED), sequence, "manual"))
+        );
+    }
+
+    #[rstest]
+    #[case::reason_with_underscore("1893456000_before_reset", Some((CREATED, 1, "before_reset")))]
+    #[case::not_a_timestamp("notes_manual", None)]
+    #[case::bad_sequence("1893456000-x_manual", None)]
+    fn parses_names(#[case] name: &str, #[case] expected: Option<(i64, u32, &str)>) {
+        assert_eq!(
+            parse_name(name),
+            expected.map(|(created, sequence, reason)| (
+                UnixTimestamp::new(created),
+                sequence,
+                reason
+            ))
+        );
+    }
+}
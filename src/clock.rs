@@ -0,0 +1,57 @@
+//! Where the current time comes from and how waiting is done. Code that waits for a time uses a
+//! [`Clock`] so that tests can control the time instead of actually waiting
+
+use std::{fmt::Debug, future::Future, pin::Pin, time::Duration};
+
+use crate::model::schedule::UnixTimestamp;
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> anyhow::Result<UnixTimestamp>;
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Uses the system's time
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> anyhow::Result<UnixTimestamp> {
+        UnixTimestamp::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Starts at a fixed time and moves forward with tokio's time. When tokio's time is paused (see
+/// [`tokio::time::pause`]) time only moves when the test advances it or everything is waiting
+#[cfg(test)]
+#[derive(Debug)]
+pub struct TestClock {
+    start: UnixTimestamp,
+    started: tokio::time::Instant,
+}
+
+#[cfg(test)]
+impl TestClock {
+    pub fn new(start: UnixTimestamp) -> Self {
+        Self {
+            start,
+            started: tokio::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for TestClock {
+    fn now(&self) -> anyhow::Result<UnixTimestamp> {
+        let elapsed: i64 = self.started.elapsed().as_secs().try_into()?;
+        Ok(UnixTimestamp::new(self.start.0 + elapsed))
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
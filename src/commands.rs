@@ -1,19 +1,35 @@
 //! Groups all the bot commands together. These then delegate to the model as needed
 
-use poise::serenity_prelude::Mentionable;
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponse, Mentionable,
+    },
+};
 use tracing::{error, info, instrument, warn};
 
 use crate::{
     AuthorPreferredDisplay as _, Context, Data,
     commands::{
+        admin::admin,
         general::{help, ping, register, uptime},
-        schedule::schedule,
+        schedule::schedule_command,
+        status::status,
         unranked_cmd::unranked,
     },
+    metrics::CommandOutcome,
 };
-pub use unranked_cmd::do_start_event;
+pub use on_error::on_error;
+pub use schedule::do_objective;
+pub use unranked_cmd::do_start_event_reminder;
+mod admin;
 mod general;
+mod on_error;
 mod schedule;
+mod status;
 mod unranked_cmd;
 
 /// Common info added to tracing for functions
@@ -41,13 +57,100 @@ async fn call_to_parent_command(ctx: Context<'_>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// How long the user has to press one of the confirmation buttons
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Shows the prompt (and embed if provided) with Confirm/Cancel buttons.
+///
+/// Returns true iff the author of the command pressed Confirm before the timeout.
+/// The buttons are removed afterwards and the outcome added to the message
+#[instrument(skip(ctx, embed))]
+async fn ask_confirmation(
+    ctx: Context<'_>,
+    prompt: String,
+    embed: Option<CreateEmbed>,
+) -> anyhow::Result<bool> {
+    info!("START");
+    let confirm_id = format!("{}confirm", ctx.id());
+    let cancel_id = format!("{}cancel", ctx.id());
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Confirm")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let mut reply = CreateReply::default()
+        .content(&prompt)
+        .components(vec![buttons]);
+    if let Some(embed) = embed.clone() {
+        reply = reply.embed(embed);
+    }
+    let handle = ctx.send(reply).await?;
+
+    let press = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .custom_ids(vec![confirm_id.clone(), cancel_id])
+        .timeout(CONFIRMATION_TIMEOUT)
+        .await;
+    let (is_confirmed, outcome) = match &press {
+        Some(press) if press.data.custom_id == confirm_id => (true, "Confirmed"),
+        Some(_) => (false, "Cancelled"),
+        None => (false, "Timed out. Nothing was done"),
+    };
+    info!(outcome);
+    if let Some(press) = press {
+        press
+            .create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+    }
+
+    // Remove the buttons so they cannot be pressed again
+    let mut edit = CreateReply::default()
+        .content(format!("{prompt}\n**{outcome}**"))
+        .components(vec![]);
+    if let Some(embed) = embed {
+        edit = edit.embed(embed);
+    }
+    handle.edit(ctx, edit).await?;
+    info!("END");
+    Ok(is_confirmed)
+}
+
+/// Records each command that completed successfully
+pub async fn record_command_success(ctx: Context<'_>) {
+    ctx.data()
+        .inner
+        .shared_config
+        .metrics
+        .record_command(&ctx.command().qualified_name, CommandOutcome::Success);
+}
+
+/// Used as the global command check so that no new commands are started once the bot is shutting
+/// down
+pub async fn refuse_during_shutdown(ctx: Context<'_>) -> anyhow::Result<bool> {
+    if !ctx.data().inner.shared_config.health.is_shutting_down() {
+        return Ok(true);
+    }
+    info!(
+        "Refused {:?} because the bot is shutting down",
+        ctx.command().qualified_name
+    );
+    ctx.reply("The bot is restarting. Please try again in a few minutes")
+        .await?;
+    Ok(false)
+}
+
 pub fn commands_list() -> Vec<poise::Command<Data, anyhow::Error>> {
     vec![
+        admin(),
         general::version(),
         help(),
         ping(),
         register(),
-        schedule(),
+        schedule_command(),
+        status(),
         unranked(),
         uptime(),
     ]
@@ -77,6 +180,11 @@ async fn is_auth(ctx: Context<'_>) -> anyhow::Result<bool> {
             .await?;
     };
     if !result {
+        ctx.data()
+            .inner
+            .shared_config
+            .metrics
+            .record_auth_denial(&ctx.command().qualified_name);
         warn!(
             "User: {:?} ({}) attempted to execute {:?} but they did not have role# {role_id}.",
             ctx.author().name,
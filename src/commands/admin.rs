@@ -0,0 +1,84 @@
+//! Groups the commands only available to the owners of the bot
+
+use poise::serenity_prelude::CreateEmbed;
+use tracing::{info, instrument};
+
+use self::{backup::backup, export::export, import::import, quarantine::quarantine};
+use crate::{
+    Context,
+    commands::{ask_confirmation, call_to_parent_command},
+    model::snapshot::Snapshot,
+};
+
+mod backup;
+mod export;
+mod import;
+mod quarantine;
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    owners_only,
+    subcommand_required,
+    subcommands("backup", "export", "import", "quarantine")
+)]
+#[instrument(name = "admin", skip(ctx))]
+/// Commands for the owners of the bot to manage its data
+pub async fn admin(ctx: Context<'_>) -> anyhow::Result<()> {
+    call_to_parent_command(ctx).await
+}
+
+/// Shows what would change if the snapshot was restored and restores it if confirmed
+#[instrument(skip(ctx, snapshot))]
+async fn confirm_and_restore(
+    ctx: Context<'_>,
+    snapshot: Snapshot,
+    source: &str,
+) -> anyhow::Result<()> {
+    info!("START");
+    let snapshot_data = snapshot.to_data()?;
+    let data = ctx.data();
+    let mut description = format!(
+        "Ideas: {} ➡️ {}\nScores: {} ➡️ {}\nScheduled tasks: {} ➡️ {}",
+        data.inner.unranked.ideas_len()?,
+        snapshot_data.ideas.len(),
+        data.inner.unranked.scores_len()?,
+        snapshot_data.scores.len(),
+        data.schedule_len()?,
+        snapshot_data.scheduled_tasks.len(),
+    );
+    let overdue = snapshot_data
+        .scheduled_tasks
+        .overdue(data.inner.shared_config.clock.now()?);
+    if !overdue.is_empty() {
+        description.push_str(&format!(
+            "\n\n⚠️ Scheduled tasks already due (handled by their misfire policy):\n- {}",
+            overdue.join("\n- ")
+        ));
+    }
+    if !snapshot_data.skipped_keys.is_empty() {
+        description.push_str(&format!(
+            "\nNot restored: {}",
+            snapshot_data.skipped_keys.join(", ")
+        ));
+    }
+    let embed = CreateEmbed::new()
+        .title("Restore Preview")
+        .description(description);
+    let prompt = format!(
+        "Replace the current data with {source} taken at {} (Bot version: {})?",
+        snapshot.created_at, snapshot.bot_version
+    );
+    if ask_confirmation(ctx, prompt, Some(embed)).await? {
+        let messages = data.restore_snapshot(snapshot_data)?;
+        let mut msg = format!("Restore from {source} completed");
+        for message in messages {
+            msg.push_str(&format!("\n- {message}"));
+        }
+        info!(msg);
+        ctx.reply(msg).await?;
+    }
+    info!("END");
+    Ok(())
+}
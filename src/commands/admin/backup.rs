@@ -0,0 +1,58 @@
+//! The commands to see and restore the automatic backups
+
+use poise::{CreateReply, serenity_prelude::CreateEmbed};
+use tracing::instrument;
+
+use crate::{
+    Context, backup,
+    commands::{
+        admin::confirm_and_restore, call_to_parent_command, tracing_handler_end,
+        tracing_handler_start,
+    },
+};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    owners_only,
+    subcommand_required,
+    subcommands("list", "restore")
+)]
+#[instrument(name = "admin-backup", skip(ctx))]
+/// Commands for the automatic backups of the data
+pub async fn backup(ctx: Context<'_>) -> anyhow::Result<()> {
+    call_to_parent_command(ctx).await
+}
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-backup-list", skip(ctx))]
+/// Lists the available backups (newest first)
+pub async fn list(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let entries = backup::list()?;
+    let description = if entries.is_empty() {
+        "No backups found".to_string()
+    } else {
+        entries
+            .iter()
+            .map(|entry| format!("- {entry}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let embed = CreateEmbed::new().title("Backups").description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-backup-restore", skip(ctx))]
+/// Replaces ideas, scores and scheduled tasks with the ones from a backup (asks for confirmation)
+pub async fn restore(
+    ctx: Context<'_>,
+    #[description = "The name of the backup as shown by list"] name: String,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let snapshot = backup::load(&name)?;
+    confirm_and_restore(ctx, snapshot, &format!("the backup `{name}`")).await?;
+    tracing_handler_end()
+}
@@ -0,0 +1,34 @@
+//! The command to download the bot's data
+
+use poise::{CreateReply, serenity_prelude::CreateAttachment};
+use tracing::{info, instrument};
+
+use crate::{
+    Context,
+    commands::{tracing_handler_end, tracing_handler_start},
+    model::snapshot::Snapshot,
+};
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-export", skip(ctx))]
+/// Sends a snapshot of all the stored data as a file
+pub async fn export(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let snapshot = Snapshot::create(ctx.data().inner.shared_config).await?;
+    let msg = format!(
+        "Snapshot of {} keys taken at {} (Bot version: {})",
+        snapshot.entries.len(),
+        snapshot.created_at,
+        snapshot.bot_version
+    );
+    info!(msg);
+    let attachment = CreateAttachment::bytes(snapshot.to_json()?, snapshot.file_name());
+    ctx.send(
+        CreateReply::default()
+            .content(msg)
+            .attachment(attachment)
+            .ephemeral(true),
+    )
+    .await?;
+    tracing_handler_end()
+}
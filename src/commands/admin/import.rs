@@ -0,0 +1,34 @@
+//! The command to replace the bot's data with a previously exported snapshot
+
+use poise::serenity_prelude::Attachment;
+use tracing::instrument;
+
+use crate::{
+    Context,
+    commands::{admin::confirm_and_restore, tracing_handler_end, tracing_handler_start},
+    model::{snapshot::Snapshot, versioned},
+    user_error::{OrUserError as _, UserError},
+};
+
+/// Larger files are rejected without downloading them
+const MAX_SNAPSHOT_SIZE_BYTES: u32 = 10 * 1024 * 1024;
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-import", skip(ctx, file), fields(file.filename = %file.filename))]
+/// Replaces ideas, scores and scheduled tasks with the ones from a snapshot (asks for confirmation)
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "A snapshot file created by export"] file: Attachment,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    if file.size > MAX_SNAPSHOT_SIZE_BYTES {
+        anyhow::bail!(UserError::new(format!(
+            "File is too large ({} bytes). Max size is {MAX_SNAPSHOT_SIZE_BYTES} bytes",
+            file.size
+        )));
+    }
+    let content = String::from_utf8(file.download().await?).or_user_error()?;
+    let snapshot: Snapshot = versioned::from_json(&content).or_user_error()?;
+    confirm_and_restore(ctx, snapshot, &format!("the snapshot {:?}", file.filename)).await?;
+    tracing_handler_end()
+}
@@ -0,0 +1,75 @@
+//! The commands to inspect and restore data that failed to load
+
+use anyhow::Context as _;
+use poise::{CreateReply, serenity_prelude::CreateEmbed};
+use tracing::{info, instrument};
+
+use crate::{
+    Context,
+    commands::{call_to_parent_command, tracing_handler_end, tracing_handler_start},
+};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    owners_only,
+    subcommand_required,
+    subcommands("list", "restore")
+)]
+#[instrument(name = "admin-quarantine", skip(ctx))]
+/// Commands for stored data that was moved aside because it could not be read
+pub async fn quarantine(ctx: Context<'_>) -> anyhow::Result<()> {
+    call_to_parent_command(ctx).await
+}
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-quarantine-list", skip(ctx))]
+/// Lists the quarantined entries
+pub async fn list(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let entries = ctx.data().inner.shared_config.quarantine_list()?;
+    let description = if entries.is_empty() {
+        "Nothing in quarantine".to_string()
+    } else {
+        entries
+            .iter()
+            .map(|entry| format!("- {entry}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let embed = CreateEmbed::new()
+        .title("Quarantined Data")
+        .description(description);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(prefix_command, slash_command, owners_only)]
+#[instrument(name = "admin-quarantine-restore", skip(ctx))]
+/// Replaces the current data with a quarantined entry (it must be readable now, fix it first if needed)
+pub async fn restore(
+    ctx: Context<'_>,
+    #[description = "The name of the entry as shown by list"] name: String,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let shared_config = ctx.data().inner.shared_config;
+    let (entry, content) = shared_config.quarantine_get(&name)?;
+    // The quarantined copy is only removed once the restored value is safely written
+    let (pending_save, messages) = ctx
+        .data()
+        .replace_kv_from_json(&entry.original_key, &content)?;
+    pending_save.flushed().await.with_context(|| {
+        format!(
+            "failed to save the restored value for `{}`. `{}` was kept in quarantine",
+            entry.original_key, entry.name
+        )
+    })?;
+    shared_config.quarantine_remove(&entry.name)?;
+    let mut msg = format!("Restored `{}` from {entry}", entry.original_key);
+    for message in messages {
+        msg.push_str(&format!("\n- {message}"));
+    }
+    info!(msg);
+    ctx.reply(msg).await?;
+    tracing_handler_end()
+}
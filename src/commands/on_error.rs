@@ -0,0 +1,115 @@
+//! Handles errors from the framework. Unexpected failures inside commands get a reference ID so that
+//! what the user saw can be matched to the logs and are reported to the bot status channel. Errors
+//! caused by the user's input ([`UserError`]) are only shown to the user
+
+use std::hash::{BuildHasher as _, RandomState};
+
+use poise::{CreateReply, FrameworkError};
+use tracing::{error, info, warn};
+
+use crate::{Context, Data, metrics::CommandOutcome, user_error::UserError};
+
+/// The most characters of the error that are included in the status channel report (Discord
+/// messages are limited to 2000)
+const MAX_REPORT_ERROR_CHARS: usize = 1500;
+
+/// Records commands that did not complete successfully then responds to the error
+pub async fn on_error(error: FrameworkError<'_, Data, anyhow::Error>) {
+    if let Some(ctx) = error.ctx() {
+        let outcome = match &error {
+            FrameworkError::Command { error, .. } if UserError::is_cause_of(error) => {
+                CommandOutcome::InvalidArguments
+            }
+            FrameworkError::Command { .. } | FrameworkError::CommandPanic { .. } => {
+                CommandOutcome::Failed
+            }
+            FrameworkError::ArgumentParse { .. }
+            | FrameworkError::CommandStructureMismatch { .. } => CommandOutcome::InvalidArguments,
+            _ => CommandOutcome::Refused,
+        };
+        ctx.data()
+            .inner
+            .shared_config
+            .metrics
+            .record_command(&ctx.command().qualified_name, outcome);
+    }
+    match error {
+        FrameworkError::Command { error, ctx, .. } if UserError::is_cause_of(&error) => {
+            info!("Command refused because of the user's input: {error:#}");
+            if let Err(e) = ctx.reply(format!("❌ {error:#}")).await {
+                error!("failed to tell user about error: {e:?}");
+            }
+        }
+        FrameworkError::Command { error, ctx, .. } => {
+            report_failure(ctx, &error.to_string(), &error).await;
+        }
+        FrameworkError::CommandPanic { payload, ctx, .. } => {
+            let error = anyhow::anyhow!(
+                "command panicked: {}",
+                payload.as_deref().unwrap_or("unknown panic")
+            );
+            report_failure(ctx, "Internal Error", &error).await;
+        }
+        // The default responses for the rest are about how the command was used so are left as is
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                error!("failed to handle error: {e:?}");
+            }
+        }
+    }
+}
+
+/// Logs the full error then tells the user (only `summary`) and the bot status channel (if not
+/// rate limited)
+async fn report_failure(ctx: Context<'_>, summary: &str, error: &anyhow::Error) {
+    let reference = new_reference_id(ctx.id());
+    let command = &ctx.command().qualified_name;
+    error!(reference, command, "Command failed: {error:?}");
+
+    let reply = CreateReply::default()
+        .content(format!(
+            "❌ {summary}\nIf this keeps happening let the bot maintainers know and include the reference `{reference}`"
+        ))
+        .ephemeral(true);
+    if let Err(e) = ctx.send(reply).await {
+        error!(reference, "failed to tell user about error: {e:?}");
+    }
+
+    let shared_config = ctx.data().inner.shared_config;
+    let Some(channel) = shared_config.channel_bot_status else {
+        warn!("Not reporting error because channel_bot_status not set");
+        return;
+    };
+    let Some(suppressed) = shared_config.allow_error_report() else {
+        warn!(
+            reference,
+            "Not reporting error to avoid flooding the status channel"
+        );
+        return;
+    };
+    let full = format!("{error:#}");
+    let mut details: String = full.chars().take(MAX_REPORT_ERROR_CHARS).collect();
+    if details.len() < full.len() {
+        details.push('…');
+    }
+    let mut msg = format!(
+        "⚠️ `{command}` failed for {} (reference `{reference}`)\n```\n{details}\n```",
+        ctx.author().name
+    );
+    if suppressed > 0 {
+        msg.push_str(&format!(
+            "\n{suppressed} earlier error(s) were not posted to avoid flooding this channel. See the logs"
+        ));
+    }
+    if let Err(e) = channel.say(ctx, msg).await {
+        error!(
+            reference,
+            "failed to report error in bot status channel: {e:?}"
+        );
+    }
+}
+
+/// A short ID to find the error in the logs
+fn new_reference_id(seed: u64) -> String {
+    format!("{:06X}", RandomState::new().hash_one(seed) & 0xFF_FFFF)
+}
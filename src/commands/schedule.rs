@@ -2,23 +2,72 @@
 
 use std::num::NonZeroUsize;
 
-use poise::{CreateReply, serenity_prelude::CreateEmbed};
+use poise::{
+    CreateReply,
+    serenity_prelude::{CacheHttp, ChannelId, CreateEmbed},
+};
 use tracing::{info, instrument};
 
 use crate::{
-    Context,
-    commands::{call_to_parent_command, is_auth, tracing_handler_end, tracing_handler_start},
+    Context, Data,
+    commands::{
+        ask_confirmation, call_to_parent_command, is_auth, tracing_handler_end,
+        tracing_handler_start,
+        unranked_cmd::{
+            do_leaderboard_post, do_season_end, do_start_event, do_voting_closes_reminder,
+        },
+    },
     model::schedule::{
-        Objective, OutcomeCreateScheduledTask, ScheduledTaskId, ScheduledTasks, UnixTimestamp,
+        Objective, ScheduledTaskId, ScheduledTasks, edit::TaskEdit, history::ExecutionHistory,
+        misfire::MisfirePolicy, recurrence::Recurrence, time_input,
     },
+    user_error::OrUserError as _,
 };
 
+/// Descriptions of the parameters shared by the subcommands. They are filled in by
+/// [`schedule_command`] as `#[description]` only accepts a literal. A subcommand that needs
+/// different wording still uses `#[description]` (eg. `edit` where leaving a value out keeps it)
+const WHEN_DESCRIPTION: &str =
+    r#"When (eg. "2026-11-01 18:00 UTC", "in 3 days 2h", "next friday 20:00")"#;
+const REPEAT_DESCRIPTION: &str = r#"Optional. Repeat every this many days or at the times of a cron expression in UTC (eg. "0 12 * * 1")"#;
+const MISFIRE_DESCRIPTION: &str =
+    r#"Optional. If missed while down: "run", "skip" (default) or max hours late to still run"#;
+
+/// The [`schedule`] command with the shared parameter descriptions filled in
+pub fn schedule_command() -> poise::Command<Data, anyhow::Error> {
+    let mut result = schedule();
+    for subcommand in &mut result.subcommands {
+        for parameter in &mut subcommand.parameters {
+            if parameter.description.is_some() {
+                continue;
+            }
+            parameter.description = match parameter.name.as_str() {
+                "when" => Some(WHEN_DESCRIPTION.to_string()),
+                "repeat" => Some(REPEAT_DESCRIPTION.to_string()),
+                "misfire" => Some(MISFIRE_DESCRIPTION.to_string()),
+                _ => None,
+            };
+        }
+    }
+    result
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
     track_edits,
     subcommand_required,
-    subcommands("set_unranked", "display", "cancel")
+    subcommands(
+        "set_unranked",
+        "announce",
+        "voting_reminder",
+        "leaderboard",
+        "season_end",
+        "display",
+        "history",
+        "edit",
+        "cancel"
+    )
 )]
 #[instrument(name = "schedule", skip(ctx))]
 /// Commands related to scheduling
@@ -38,34 +87,178 @@ pub async fn schedule(ctx: Context<'_>) -> anyhow::Result<()> {
 /// Sets when the next unranked is expected to start (use no args for more info)
 pub async fn set_unranked(
     ctx: Context<'_>,
-    #[description = "A unix timestamp. If you need more info just leave out argument for more info to be returned"]
-    unix_timestamp: Option<i32>,
+    #[description = "When it starts (eg. \"2026-11-01 18:00 UTC\", \"in 3 days 2h\"). Leave out for more info"]
+    when: Option<String>,
+    repeat: Option<String>,
+    misfire: Option<String>,
 ) -> anyhow::Result<()> {
     tracing_handler_start(&ctx).await;
-    if let Some(unix_timestamp) = unix_timestamp {
-        let timestamp = UnixTimestamp::new(unix_timestamp);
-        let outcome = ctx
-            .data()
-            .schedule_create_task(Objective::UnrankedStartEvent, timestamp)?;
-        let mut msg = format!("Unranked Event Start Scheduled for {timestamp}");
-        if let OutcomeCreateScheduledTask::Replaced(prev) = outcome {
-            use std::fmt::Write as _;
-            write!(msg, "\nCancelled previous schedule for {prev}")?;
-        }
-        ctx.reply(msg).await?;
+    if let Some(when) = when {
+        create_task(ctx, Objective::UnrankedStartEvent, &when, repeat, misfire).await?;
     } else {
         info!("Info given, command not executed");
-        ctx.reply(
-            "This command expects a unix timestamp.
+        ctx.reply(format!(
+            "This command expects a date/time for when the event starts.
+{}
 For help with generating a timestamp see <https://c-git.github.io/misc/discord/>
-you can test your timestamp by pasting the string given on the site in discord.
-Note: the command expects **ONLY** the number part",
-        )
+You will be shown how the time was understood and asked to confirm before anything is scheduled",
+            time_input::FORMATS_HELP
+        ))
         .await?;
     }
     tracing_handler_end()
 }
 
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    track_edits,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "schedule-announce", skip(ctx))]
+/// Schedules a message to be posted in a channel
+pub async fn announce(
+    ctx: Context<'_>,
+    when: String,
+    #[description = "Channel to post in"] channel: ChannelId,
+    #[description = "The message to post"] message: String,
+    repeat: Option<String>,
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let objective = Objective::Announcement {
+        channel_id: channel,
+        message,
+    };
+    create_task(ctx, objective, &when, repeat, misfire).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    track_edits,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "schedule-voting_reminder", skip(ctx))]
+/// Schedules a reminder in the unranked channel that voting on ideas closes soon
+pub async fn voting_reminder(
+    ctx: Context<'_>,
+    when: String,
+    #[description = "When voting closes (same formats as when)"] closes_at: String,
+    repeat: Option<String>,
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let shared_config = ctx.data().inner.shared_config;
+    let objective = Objective::VotingClosesReminder {
+        closes_at: time_input::parse(
+            &closes_at,
+            shared_config.clock.now()?.to_date_time()?,
+            shared_config.alliance_timezone,
+        )
+        .or_user_error()?,
+    };
+    create_task(ctx, objective, &when, repeat, misfire).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    track_edits,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "schedule-leaderboard", skip(ctx))]
+/// Schedules posting the current unranked scores in a channel
+pub async fn leaderboard(
+    ctx: Context<'_>,
+    when: String,
+    #[description = "Channel to post in"] channel: ChannelId,
+    repeat: Option<String>,
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let objective = Objective::LeaderboardPost {
+        channel_id: channel,
+    };
+    create_task(ctx, objective, &when, repeat, misfire).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    track_edits,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "schedule-season_end", skip(ctx))]
+/// Schedules announcing the end of the unranked season with the final standings
+pub async fn season_end(
+    ctx: Context<'_>,
+    when: String,
+    #[description = "Channel to post in"] channel: ChannelId,
+    repeat: Option<String>,
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let objective = Objective::SeasonEnd {
+        channel_id: channel,
+    };
+    create_task(ctx, objective, &when, repeat, misfire).await?;
+    tracing_handler_end()
+}
+
+/// Parses the options shared by all scheduling commands, then once confirmed creates the task and
+/// replies with the outcome
+async fn create_task(
+    ctx: Context<'_>,
+    objective: Objective,
+    when: &str,
+    repeat: Option<String>,
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    use std::fmt::Write as _;
+    let shared_config = ctx.data().inner.shared_config;
+    let now = shared_config.clock.now()?.to_date_time()?;
+    let timezone = shared_config.alliance_timezone;
+    let timestamp = time_input::parse(when, now, timezone).or_user_error()?;
+    let recurrence = repeat
+        .map(|x| x.parse::<Recurrence>())
+        .transpose()
+        .or_user_error()?;
+    let misfire_policy = misfire
+        .map(|x| x.parse::<MisfirePolicy>())
+        .transpose()
+        .or_user_error()?
+        .unwrap_or_default();
+    let mut msg = format!(
+        "{objective} scheduled for {timestamp} ({})",
+        timestamp.format_in(timezone)
+    );
+    if let Some(recurrence) = &recurrence {
+        write!(msg, "\nRepeats {recurrence}")?;
+    }
+    write!(msg, "\nIf missed: {misfire_policy}")?;
+    if !ask_confirmation(ctx, format!("Is this correct?\n{msg}"), None).await? {
+        return Ok(());
+    }
+    let id = ctx
+        .data()
+        .schedule_create_task(objective, timestamp, recurrence, misfire_policy)?;
+    write!(msg, "\nTask ID: {id} (use it to cancel)")?;
+    ctx.reply(msg).await?;
+    Ok(())
+}
+
 #[poise::command(prefix_command, slash_command, track_edits, aliases("disp"))]
 #[instrument(name = "schedule-display", skip(ctx))]
 /// Shows the scheduled tasks [aliases("disp")]
@@ -80,6 +273,90 @@ pub async fn display(ctx: Context<'_>) -> anyhow::Result<()> {
     tracing_handler_end()
 }
 
+#[poise::command(prefix_command, slash_command, track_edits)]
+#[instrument(name = "schedule-history", skip(ctx))]
+/// Shows the scheduled tasks that have run, most recent first
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "Optional. The page to show (default 1)"] page: Option<NonZeroUsize>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let page = page.unwrap_or(NonZeroUsize::MIN);
+    let (history_as_string, page_count) = ctx.data().schedule_history_page(page)?;
+    let embed = CreateEmbed::new()
+        .title(format!(
+            "{} (page {page} of {page_count})",
+            ExecutionHistory::DISPLAY_TITLE
+        ))
+        .description(history_as_string);
+    let builder = CreateReply::default().embed(embed);
+    ctx.send(builder).await?;
+    tracing_handler_end()
+}
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    track_edits,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "schedule-edit", skip(ctx))]
+/// Changes a scheduled task (only the values given are changed)
+#[expect(clippy::too_many_arguments)]
+pub async fn edit(
+    ctx: Context<'_>,
+    #[description = "The task's ID (see display)"] id: NonZeroUsize,
+    when: Option<String>,
+    #[description = "New channel to post in (not for unranked start or voting reminders)"]
+    channel: Option<ChannelId>,
+    #[description = "New message (announcements only)"] message: Option<String>,
+    #[description = "New time voting closes (voting reminders only)"] closes_at: Option<String>,
+    #[description = "Repeat every this many days, a cron expression in UTC or \"none\" to stop repeating"]
+    repeat: Option<String>,
+    #[description = "If missed while down: \"run\", \"skip\" or max hours late to still run"]
+    misfire: Option<String>,
+) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let id: ScheduledTaskId = id.into();
+    let shared_config = ctx.data().inner.shared_config;
+    let now = shared_config.clock.now()?.to_date_time()?;
+    let timezone = shared_config.alliance_timezone;
+    let edit = TaskEdit {
+        desired_execution_timestamp: when
+            .map(|x| time_input::parse(&x, now, timezone))
+            .transpose()
+            .or_user_error()?,
+        channel_id: channel,
+        message,
+        closes_at: closes_at
+            .map(|x| time_input::parse(&x, now, timezone))
+            .transpose()
+            .or_user_error()?,
+        recurrence: repeat
+            .map(|x| {
+                if x.trim().eq_ignore_ascii_case("none") {
+                    Ok(None)
+                } else {
+                    x.parse::<Recurrence>().map(Some)
+                }
+            })
+            .transpose()
+            .or_user_error()?,
+        misfire_policy: misfire
+            .map(|x| x.parse::<MisfirePolicy>())
+            .transpose()
+            .or_user_error()?,
+    };
+    let (before, after) = ctx.data().schedule_edit_task(id, edit)?;
+    ctx.reply(format!(
+        "Task {id} updated\n**Before:** {before}\n**After:** {after}"
+    ))
+    .await?;
+    tracing_handler_end()
+}
+
 #[poise::command(
     hide_in_help,
     prefix_command,
@@ -89,13 +366,20 @@ pub async fn display(ctx: Context<'_>) -> anyhow::Result<()> {
     check = "is_auth"
 )]
 #[instrument(name = "schedule-cancel", skip(ctx))]
-/// Cancel a scheduled event
+/// Cancel a scheduled event (asks for confirmation)
 pub async fn cancel(
     ctx: Context<'_>,
-    #[description = "See display to get valid values"] id: NonZeroUsize,
+    #[description = "The task's ID (see display)"] id: NonZeroUsize,
 ) -> anyhow::Result<()> {
     tracing_handler_start(&ctx).await;
     let id: ScheduledTaskId = id.into();
+    let prompt = format!(
+        "Cancel this scheduled task?\n{}",
+        ctx.data().schedule_task_as_string(id)?
+    );
+    if !ask_confirmation(ctx, prompt, None).await? {
+        return tracing_handler_end();
+    }
     let scheduled_task = ctx.data().schedule_cancel_task_by_id(id)?;
     ctx.reply(format!(
         "{} cancelled for {}",
@@ -104,3 +388,54 @@ pub async fn cancel(
     .await?;
     tracing_handler_end()
 }
+
+/// Carries out the objective of a scheduled task that is due
+#[instrument(skip(cache_http, data))]
+pub async fn do_objective(
+    cache_http: impl CacheHttp,
+    objective: &Objective,
+    data: &Data,
+) -> anyhow::Result<()> {
+    info!("START");
+    let channel_unranked = data.inner.shared_config.channel_unranked;
+    match objective {
+        Objective::UnrankedStartEvent => do_start_event(cache_http, channel_unranked, data).await,
+        Objective::Announcement {
+            channel_id,
+            message,
+        } => {
+            channel_id.say(&cache_http, message).await?;
+            tracing_handler_end()
+        }
+        Objective::VotingClosesReminder { closes_at } => {
+            do_voting_closes_reminder(cache_http, channel_unranked, data, *closes_at).await
+        }
+        Objective::LeaderboardPost { channel_id } => {
+            do_leaderboard_post(cache_http, *channel_id, data).await
+        }
+        Objective::SeasonEnd { channel_id } => do_season_end(cache_http, *channel_id, data).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_parameter_has_a_description() {
+        for subcommand in schedule_command().subcommands {
+            for parameter in subcommand.parameters {
+                let description = parameter.description.unwrap_or_else(|| {
+                    panic!("{} {} has no description", subcommand.name, parameter.name)
+                });
+                // Discord's limit (only checked by the macro for literals)
+                assert!(
+                    description.chars().count() <= 100,
+                    "{} {} description is too long",
+                    subcommand.name,
+                    parameter.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Groups the commands related to the status of the bot itself
+
+use poise::{CreateReply, serenity_prelude::CreateEmbed};
+use tracing::instrument;
+
+use crate::{
+    Context,
+    commands::{call_to_parent_command, tracing_handler_end, tracing_handler_start},
+    heartbeat::downtime::DowntimeHistory,
+};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    track_edits,
+    subcommand_required,
+    subcommands("downtime")
+)]
+#[instrument(name = "status", skip(ctx))]
+/// Commands related to the status of the bot
+pub async fn status(ctx: Context<'_>) -> anyhow::Result<()> {
+    call_to_parent_command(ctx).await
+}
+
+#[poise::command(prefix_command, slash_command, track_edits)]
+#[instrument(name = "status-downtime", skip(ctx))]
+/// Shows recent outages and how available the bot has been
+pub async fn downtime(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let shared_config = ctx.data().inner.shared_config;
+    let history: DowntimeHistory = shared_config
+        .load_or_default_kv(DowntimeHistory::DATA_KEY)
+        .await?;
+    let embed = CreateEmbed::new()
+        .title(DowntimeHistory::DISPLAY_TITLE)
+        .description(history.summary(shared_config.clock.now()?)?);
+    let builder = CreateReply::default().embed(embed);
+    ctx.send(builder).await?;
+    tracing_handler_end()
+}
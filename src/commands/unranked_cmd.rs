@@ -1,18 +1,29 @@
 //! Groups the commands related to the unranked challenge
 
-use poise::serenity_prelude::{CacheHttp, ChannelId};
+use poise::{
+    CreateReply,
+    serenity_prelude::{CacheHttp, ChannelId, CreateEmbed},
+};
 use tracing::{info, instrument};
 
 use self::{idea::idea, score::score};
 use crate::{
-    Context, Data,
+    AuthorPreferredDisplay as _, Context, Data, backup,
     commands::{
-        call_to_parent_command, is_auth, tracing_handler_end, tracing_handler_start,
+        ask_confirmation, call_to_parent_command, is_auth, tracing_handler_end,
+        tracing_handler_start,
         unranked_cmd::{
             idea::{display_ideas_channel, do_ideas_reset},
             score::{display_scores_channel, do_scores_reset},
         },
     },
+    model::{
+        schedule::UnixTimestamp,
+        unranked::{
+            ideas::Idea,
+            start_event_preview::{self, StartEventPreview},
+        },
+    },
 };
 
 mod idea;
@@ -24,7 +35,7 @@ mod score;
     track_edits,
     aliases("ur"),
     subcommand_required,
-    subcommands("idea", "score", "start_event")
+    subcommands("idea", "score", "start_event", "preview_start", "undo_start_event")
 )]
 #[instrument(name = "unranked", skip(ctx))]
 /// Commands related to the Unranked Challenge [aliases("ur")]
@@ -34,14 +45,102 @@ pub async fn unranked(ctx: Context<'_>) -> anyhow::Result<()> {
 
 #[poise::command(hide_in_help, prefix_command, guild_only = true, check = "is_auth")]
 #[instrument(name = "unranked-start_event", skip(ctx))]
-/// Resets ideas and scores for the start of the new event and sets the message with the leading idea
+/// Resets ideas and scores for the start of the new event and sets the message with the leading idea (asks for confirmation)
 pub async fn start_event(ctx: Context<'_>) -> anyhow::Result<()> {
     tracing_handler_start(&ctx).await;
+    let preview = ctx.data().inner.unranked.start_event_preview()?;
+    if !ask_confirmation(
+        ctx,
+        "Start a new unranked event?".to_string(),
+        Some(preview_start_embed(&preview)),
+    )
+    .await?
+    {
+        return tracing_handler_end();
+    }
     ctx.reply("Request started").await?;
     do_start_event(ctx, ctx.channel_id(), ctx.data()).await?;
     tracing_handler_end()
 }
 
+#[poise::command(prefix_command, slash_command, guild_only = true, check = "is_auth")]
+#[instrument(name = "unranked-preview_start", skip(ctx))]
+/// Shows what starting a new event would do right now without changing anything
+pub async fn preview_start(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let preview = ctx.data().inner.unranked.start_event_preview()?;
+    ctx.send(CreateReply::default().embed(preview_start_embed(&preview)))
+        .await?;
+    tracing_handler_end()
+}
+
+fn preview_start_embed(preview: &StartEventPreview) -> CreateEmbed {
+    fn list(ideas: &[Idea]) -> String {
+        if ideas.is_empty() {
+            return "[None]".to_string();
+        }
+        ideas
+            .iter()
+            .map(|idea| format!("- {idea}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    let winner = match &preview.winner {
+        Some(idea) => idea.to_string(),
+        None => "[No ideas]".to_string(),
+    };
+    let description = format!(
+        "**Winning idea**\n> {winner}\n\n\
+        **Surviving ideas** (votes get cleared)\n{}\n\n\
+        **Discarded ideas** ({} votes or less)\n{}\n\n\
+        **Scores removed:** {}\n\n\
+        **New score message**\n{}",
+        list(&preview.surviving),
+        preview.discard_threshold,
+        list(&preview.discarded),
+        preview.scores_cleared,
+        preview.new_scores,
+    );
+    CreateEmbed::new()
+        .title("Start Event Preview")
+        .description(description)
+}
+
+#[poise::command(
+    hide_in_help,
+    prefix_command,
+    slash_command,
+    guild_only = true,
+    check = "is_auth"
+)]
+#[instrument(name = "unranked-undo_start_event", skip(ctx))]
+/// Restores the ideas and scores from before the last start event (only shortly after it started)
+pub async fn undo_start_event(ctx: Context<'_>) -> anyhow::Result<()> {
+    tracing_handler_start(&ctx).await;
+    let data = ctx.data();
+    let taken_at = data
+        .inner
+        .unranked
+        .start_event_undo(data.inner.shared_config.undo_start_event_grace)?;
+    let channel_id = data.inner.shared_config.channel_unranked;
+    channel_id
+        .say(
+            ctx,
+            format!(
+                "@here The start of the unranked event was rolled back by {}. Ideas and scores are back to how they were at {taken_at}",
+                ctx.author_preferred_display().await
+            ),
+        )
+        .await?;
+    display_ideas_channel(ctx, channel_id, data, false).await?;
+    display_scores_channel(ctx, channel_id, data).await?;
+    if ctx.channel_id() != channel_id {
+        ctx.reply(format!("Start event rolled back to {taken_at}"))
+            .await?;
+    }
+    tracing_handler_end()
+}
+
 #[instrument(skip(cache_http, data))]
 pub async fn do_start_event(
     cache_http: impl CacheHttp,
@@ -49,6 +148,8 @@ pub async fn do_start_event(
     data: &Data,
 ) -> anyhow::Result<()> {
     info!("START");
+    backup::take_backup_or_report(&cache_http, data.inner.shared_config, "start_event").await;
+    data.inner.unranked.start_event_save_undo()?;
     channel_id
         .say(
             &cache_http,
@@ -69,11 +170,7 @@ pub async fn do_start_event(
     do_scores_reset(&cache_http, channel_id, data).await?;
 
     // Get message for new scores
-    let msg = if let Some(idea) = leading {
-        idea.description
-    } else {
-        "Seems there were no ideas".to_string()
-    };
+    let msg = start_event_preview::challenge_message(leading.as_ref());
 
     // Set message for new scores
     data.inner
@@ -95,3 +192,75 @@ pub async fn do_start_event(
 
     tracing_handler_end()
 }
+
+#[instrument(skip(cache_http, data))]
+pub async fn do_voting_closes_reminder(
+    cache_http: impl CacheHttp,
+    channel_id: ChannelId,
+    data: &Data,
+    closes_at: UnixTimestamp,
+) -> anyhow::Result<()> {
+    info!("START");
+    channel_id
+        .say(
+            &cache_http,
+            format!(
+                "@here Voting on ideas for the next unranked closes {closes_at}. Use `/unranked idea vote` to support the ones you want. Current ideas:"
+            ),
+        )
+        .await?;
+    display_ideas_channel(&cache_http, channel_id, data, false).await?;
+    tracing_handler_end()
+}
+
+#[instrument(skip(cache_http, data))]
+pub async fn do_leaderboard_post(
+    cache_http: impl CacheHttp,
+    channel_id: ChannelId,
+    data: &Data,
+) -> anyhow::Result<()> {
+    info!("START");
+    display_scores_channel(&cache_http, channel_id, data).await?;
+    tracing_handler_end()
+}
+
+#[instrument(skip(cache_http, data))]
+pub async fn do_season_end(
+    cache_http: impl CacheHttp,
+    channel_id: ChannelId,
+    data: &Data,
+) -> anyhow::Result<()> {
+    info!("START");
+    channel_id
+        .say(
+            &cache_http,
+            "@here The unranked season has ended! Here are the final standings:",
+        )
+        .await?;
+    display_scores_channel(&cache_http, channel_id, data).await?;
+    tracing_handler_end()
+}
+
+#[instrument(skip(cache_http, data))]
+pub async fn do_start_event_reminder(
+    cache_http: impl CacheHttp,
+    channel_id: ChannelId,
+    data: &Data,
+    starts_at: UnixTimestamp,
+) -> anyhow::Result<()> {
+    info!("START");
+    let preview = data.inner.unranked.start_event_preview()?;
+    let leading = match &preview.winner {
+        Some(idea) => format!("The leading idea is currently:\n> {idea}"),
+        None => "There are no ideas yet!".to_string(),
+    };
+    channel_id
+        .say(
+            &cache_http,
+            format!(
+                "@here The next unranked event starts {starts_at}. Ideas will be frozen and scores reset at that time.\n{leading}\nUse `/unranked idea vote` to vote for the ideas you want before then."
+            ),
+        )
+        .await?;
+    tracing_handler_end()
+}
@@ -9,9 +9,10 @@ use poise::{
 use tracing::{info, instrument};
 
 use crate::{
-    Data,
+    Data, backup,
     commands::{
-        Context, call_to_parent_command, is_auth, tracing_handler_end, tracing_handler_start,
+        Context, ask_confirmation, call_to_parent_command, is_auth, tracing_handler_end,
+        tracing_handler_start,
     },
     model::{
         unranked::ideas::{IdeaId, Ideas},
@@ -158,9 +159,19 @@ pub async fn threshold(ctx: Context<'_>, threshold: usize) -> anyhow::Result<()>
 
 #[poise::command(prefix_command, guild_only = true, check = "is_auth")]
 #[instrument(name = "unranked-idea-reset", skip(ctx))]
-/// Sets ideas back to the default
+/// Removes ideas at or below the threshold and clears votes on the rest (asks for confirmation)
 pub async fn reset(ctx: Context<'_>) -> anyhow::Result<()> {
     tracing_handler_start(&ctx).await;
+    let (total, kept) = ctx.data().inner.unranked.ideas_reset_preview()?;
+    let prompt = format!(
+        "Reset ideas? {} of the {total} ideas are at or below the threshold and will be removed. Votes on the other {kept} will be cleared.",
+        total - kept
+    );
+    let embed = display_generate_embed(ctx, ctx.data(), false).await?;
+    if !ask_confirmation(ctx, prompt, Some(embed)).await? {
+        return tracing_handler_end();
+    }
+    backup::take_backup_or_report(ctx, ctx.data().inner.shared_config, "ideas_reset").await;
     do_ideas_reset(ctx, ctx.channel_id(), ctx.data()).await?;
     ctx.reply("Ideas reset completed").await?;
     tracing_handler_end()
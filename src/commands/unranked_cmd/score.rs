@@ -1,8 +1,8 @@
 //! Groups the commands related to the scoring functionality for unranked
 
 use crate::{
-    Context, Data,
-    commands::{is_auth, tracing_handler_end, tracing_handler_start},
+    Context, Data, backup,
+    commands::{ask_confirmation, is_auth, tracing_handler_end, tracing_handler_start},
     model::{
         unranked::scores::{ScoreValue, Scores},
         user_serde::UserRecordSupport as _,
@@ -98,9 +98,18 @@ pub async fn message(ctx: Context<'_>, #[rest] msg: Option<String>) -> anyhow::R
 
 #[poise::command(hide_in_help, prefix_command, guild_only = true, check = "is_auth")]
 #[instrument(name = "unranked-score-reset", skip(ctx))]
-/// Sets scores back to the default
+/// Sets scores back to the default (asks for confirmation)
 pub async fn reset(ctx: Context<'_>) -> anyhow::Result<()> {
     tracing_handler_start(&ctx).await;
+    let prompt = format!(
+        "Reset scores? All {} scores and the message will be removed.",
+        ctx.data().inner.unranked.scores_len()?
+    );
+    let embed = display_generate_embed(ctx.data())?;
+    if !ask_confirmation(ctx, prompt, Some(embed)).await? {
+        return tracing_handler_end();
+    }
+    backup::take_backup_or_report(ctx, ctx.data().inner.shared_config, "scores_reset").await;
     do_scores_reset(&ctx, ctx.channel_id(), ctx.data()).await?;
     ctx.reply("Scores reset").await?;
     tracing_handler_end()
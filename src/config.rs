@@ -1,10 +1,29 @@
-use std::{collections::HashSet, fmt::Debug, time::Instant};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    net::SocketAddr,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use anyhow::Context as _;
+use chrono_tz::Tz;
 use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId};
-use tracing::error;
+use tracing::{error, warn};
 
-use crate::ClapConfig;
+use crate::{
+    ClapConfig,
+    clock::{Clock, SystemClock},
+    db::{
+        KvWriter, PendingSave,
+        quarantine::{self, QuarantinedEntry},
+    },
+    health::HealthState,
+    metrics::Metrics,
+    model::versioned::{self, Versioned},
+    rate_limit::RateLimiter,
+};
 
 #[derive(Debug)]
 pub struct StartupConfig {
@@ -20,6 +39,26 @@ pub struct SharedConfig {
     pub auth_role_id: RoleId,
     pub channel_unranked: ChannelId,
     pub channel_bot_status: Option<ChannelId>,
+    /// `None` if periodic backups are disabled
+    pub backup_interval: Option<Duration>,
+    pub backup_keep: NonZeroUsize,
+    /// How long after an unranked event starts it can still be undone
+    pub undo_start_event_grace: Duration,
+    /// How long before a scheduled unranked start each reminder is posted
+    pub unranked_reminder_offsets: Vec<Duration>,
+    /// Used when showing times to the alliance as a whole (eg. in status messages)
+    pub alliance_timezone: Tz,
+    /// Where the scheduler and heartbeat get the time from
+    pub clock: Arc<dyn Clock>,
+    /// `None` if the health endpoints are disabled
+    pub health_address: Option<SocketAddr>,
+    pub health: HealthState,
+    pub metrics: Metrics,
+    kv_writer: KvWriter,
+    /// Limits how many command failures are posted to the bot status channel
+    error_reports: Mutex<RateLimiter>,
+    /// Problems found during startup that should be reported once the bot is connected
+    startup_alerts: Mutex<Vec<String>>,
 }
 
 impl StartupConfig {
@@ -53,6 +92,9 @@ impl StartupConfig {
 }
 
 impl SharedConfig {
+    const ERROR_REPORTS_PER_WINDOW: usize = 5;
+    const ERROR_REPORT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
     pub fn try_new(clap_config: &ClapConfig) -> anyhow::Result<&'static Self> {
         let auth_role_id = clap_config
             .auth_role_id
@@ -70,42 +112,198 @@ impl SharedConfig {
             .context("failed to parse bot status channel id")
             .ok()
             .map(ChannelId::new);
+        let kv_store = crate::db::new_store(clap_config).context("failed to create kv store")?;
+        let metrics = Metrics::default();
+        let kv_writer = KvWriter::new(kv_store, metrics.kv().clone());
+        let backup_interval = (clap_config.backup_interval_hours > 0)
+            .then(|| Duration::from_secs(clap_config.backup_interval_hours * 60 * 60));
         let result = Box::new(Self {
             start_instant: Instant::now(),
             auth_role_id,
             channel_unranked,
             channel_bot_status,
+            backup_interval,
+            backup_keep: clap_config.backup_keep,
+            undo_start_event_grace: Duration::from_secs(
+                clap_config.undo_start_event_grace_hours * 60 * 60,
+            ),
+            unranked_reminder_offsets: clap_config
+                .unranked_reminder_hours
+                .iter()
+                .filter(|hours| **hours > 0)
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+                .collect(),
+            alliance_timezone: clap_config.alliance_timezone,
+            clock: Arc::new(SystemClock),
+            health_address: clap_config
+                .health_port
+                .map(|port| SocketAddr::new(clap_config.health_host, port)),
+            health: HealthState::default(),
+            metrics,
+            kv_writer,
+            error_reports: Mutex::new(RateLimiter::new(
+                Self::ERROR_REPORTS_PER_WINDOW,
+                Self::ERROR_REPORT_WINDOW,
+            )),
+            startup_alerts: Default::default(),
         });
         Ok(Box::leak(result))
     }
 
-    /// Doesn't actually perform the save but spawns a task to do it in the background
-    pub fn save_kv<T: serde::Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> {
-        let key = key.to_string();
-        let value = serde_json::to_string(value).context("failed to convert to json")?;
-        tokio::spawn(async move {
-            crate::db::save_kv(&key, value).await;
-        });
-        Ok(())
+    /// A config with an in memory store that gets the time from `clock` (leaked like the real one)
+    #[cfg(test)]
+    pub fn new_for_test(clock: Arc<dyn Clock>) -> &'static Self {
+        let metrics = Metrics::default();
+        let kv_writer = KvWriter::new(
+            Arc::new(crate::db::SqliteStore::in_memory()),
+            metrics.kv().clone(),
+        );
+        Box::leak(Box::new(Self {
+            start_instant: Instant::now(),
+            auth_role_id: RoleId::new(1),
+            channel_unranked: ChannelId::new(1),
+            channel_bot_status: None,
+            backup_interval: None,
+            backup_keep: NonZeroUsize::MIN,
+            undo_start_event_grace: Duration::from_secs(2 * 60 * 60),
+            unranked_reminder_offsets: Vec::new(),
+            alliance_timezone: Tz::UTC,
+            clock,
+            health_address: None,
+            health: HealthState::default(),
+            metrics,
+            kv_writer,
+            error_reports: Mutex::new(RateLimiter::new(
+                Self::ERROR_REPORTS_PER_WINDOW,
+                Self::ERROR_REPORT_WINDOW,
+            )),
+            startup_alerts: Default::default(),
+        }))
+    }
+
+    /// Doesn't actually perform the save but queues it for the background writer of that key.
+    /// The returned value can be used to wait until the save has been written
+    pub fn save_kv<T: Versioned>(&self, key: &str, value: &T) -> anyhow::Result<PendingSave> {
+        let value = versioned::to_json(value)?;
+        self.kv_writer.save(key, value)
     }
 
-    pub async fn load_or_default_kv<T: serde::de::DeserializeOwned + Default>(
-        &self,
-        key: &str,
-    ) -> T {
-        let Some(content) = crate::db::load_kv(key).await else {
-            return T::default();
+    /// Waits until all saves queued so far have been written
+    pub async fn flush_kv(&self) -> anyhow::Result<()> {
+        self.kv_writer.flush().await
+    }
+
+    /// Returns `None` if nothing has been saved for the key
+    pub async fn load_kv<T: Versioned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        let Some(content) = self.kv_writer.store().load(key)? else {
+            return Ok(None);
         };
-        match serde_json::from_str(&content) {
-            Ok(x) => x,
+        versioned::from_json(&content)
+            .with_context(|| {
+                format!("failed to convert content extracted from the database: {content:?}")
+            })
+            .map(Some)
+    }
+
+    /// The keys of all the stored data (excludes quarantined copies)
+    pub fn kv_keys(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .kv_writer
+            .store()
+            .keys()?
+            .into_iter()
+            .filter(|key| !quarantine::is_quarantine_key(key))
+            .collect())
+    }
+
+    /// Returns the content exactly as stored
+    pub fn load_raw_kv(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.kv_writer.store().load(key)
+    }
+
+    /// Returns the default if nothing was stored for the key.
+    ///
+    /// If the stored value cannot be read it is moved into quarantine (and an alert queued) before
+    /// returning the default so that later saves cannot overwrite it. If that is not possible an
+    /// error is returned instead as continuing would lose the stored value
+    pub async fn load_or_default_kv<T: Versioned + Default>(&self, key: &str) -> anyhow::Result<T> {
+        let store = self.kv_writer.store();
+        let Some(content) = store
+            .load(key)
+            .with_context(|| format!("failed to load key: {key}"))?
+        else {
+            return Ok(T::default());
+        };
+        match versioned::from_json(&content) {
+            Ok(x) => Ok(x),
             Err(err_msg) => {
                 error!(
                     ?err_msg,
                     ?content,
                     "Failed to convert content extracted from the database"
                 );
-                T::default()
+                let name = quarantine::quarantine(store, &*self.clock, key, &content).with_context(|| {
+                    format!(
+                        "failed to quarantine unreadable content for key: {key}. Refusing to continue with an empty value as it would overwrite the stored one"
+                    )
+                })?;
+                self.add_startup_alert(format!(
+                    "⚠️ Stored data for `{key}` could not be read ({err_msg:#}). It was moved to quarantine as `{name}` and `{key}` was started empty. See `/admin quarantine` to restore it."
+                ));
+                Ok(T::default())
+            }
+        }
+    }
+
+    /// Queues a message to be posted to the bot status channel once the bot is connected
+    pub fn add_startup_alert(&self, msg: String) {
+        warn!(msg);
+        match self.startup_alerts.lock() {
+            Ok(mut guard) => guard.push(msg),
+            Err(e) => error!("failed to lock mutex to add startup alert because '{e}"),
+        }
+    }
+
+    /// Returns the alerts collected so far and clears them
+    pub fn take_startup_alerts(&self) -> Vec<String> {
+        match self.startup_alerts.lock() {
+            Ok(mut guard) => std::mem::take(&mut guard),
+            Err(e) => {
+                error!("failed to lock mutex to take startup alerts because '{e}");
+                Vec::new()
             }
         }
     }
+
+    /// Returns the number of error reports suppressed since the last one if this report may be
+    /// posted to the bot status channel and `None` if it should be skipped to avoid flooding it
+    pub fn allow_error_report(&self) -> Option<usize> {
+        let now = match self.clock.now() {
+            Ok(now) => now,
+            Err(e) => {
+                error!("failed to get time to rate limit error reports: {e:?}");
+                return None;
+            }
+        };
+        match self.error_reports.lock() {
+            Ok(mut guard) => guard.check(now),
+            Err(e) => {
+                error!("failed to lock mutex to rate limit error reports because '{e}");
+                None
+            }
+        }
+    }
+
+    pub fn quarantine_list(&self) -> anyhow::Result<Vec<QuarantinedEntry>> {
+        quarantine::list(self.kv_writer.store())
+    }
+
+    /// Returns the entry and its content
+    pub fn quarantine_get(&self, name: &str) -> anyhow::Result<(QuarantinedEntry, String)> {
+        quarantine::get(self.kv_writer.store(), name)
+    }
+
+    pub fn quarantine_remove(&self, name: &str) -> anyhow::Result<()> {
+        self.kv_writer.store().delete(name)
+    }
 }
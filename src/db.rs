@@ -1,67 +1,65 @@
-use std::{fs, io::Write as _, path::PathBuf};
+//! Storage for the key value store used to persist the bot's data.
+//!
+//! The rest of the application only talks to the [`KvStore`] trait (via [`crate::SharedConfig`])
+//! so that the backend can be chosen at startup without changing how the model is persisted
 
-use tracing::error;
+use std::{fmt::Debug, sync::Arc};
 
-const KEY_VALUE_STORE_FOLDER: &str = "KV";
+use anyhow::Context as _;
+use tracing::info;
 
-/// Returns a path if able to be created else logs error and returns `None`
-fn get_file_path(key: &str) -> Option<PathBuf> {
-    let mut result = PathBuf::from(KEY_VALUE_STORE_FOLDER);
-    match std::fs::create_dir_all(&result) {
-        Ok(()) => {}
-        Err(err_msg) => {
-            error!(
-                ?err_msg,
-                "Failed to create parent directory for key value store: {result:?}"
-            );
-            return None;
-        }
-    };
-    result = result.join(key);
-    if !result.add_extension("json") {
-        error!("Unable to add json extension to path: {result:?}");
-    }
-    Some(result)
+use crate::ClapConfig;
+
+pub use self::{
+    file::FileStore,
+    sqlite::SqliteStore,
+    writer::{KvWriter, PendingSave},
+};
+
+mod file;
+pub mod quarantine;
+mod sqlite;
+mod writer;
+
+/// A place where values (already serialized) can be stored and retrieved by key
+pub trait KvStore: Debug + Send + Sync {
+    /// Stores the value replacing any previous value for that key.
+    /// A failed save must leave the previous value intact
+    fn save(&self, key: &str, value: &str) -> anyhow::Result<()>;
+
+    /// Returns `None` if nothing has been stored for the key yet
+    fn load(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    /// Removes the key if it exists
+    fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// All keys currently stored (sorted)
+    fn keys(&self) -> anyhow::Result<Vec<String>>;
 }
 
-pub async fn save_kv(key: &str, value: String) {
-    let Some(path) = get_file_path(key) else {
-        return;
-    };
+/// The backends available to store the data
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+pub enum StorageBackend {
+    /// A folder with one json file per key
+    #[default]
+    File,
 
-    let mut file = match std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)
-    {
-        Ok(file) => file,
-        Err(err_msg) => {
-            error!(
-                ?err_msg,
-                "Failed to save content for key: {key} to kv store (file creation failed)"
-            );
-            return;
-        }
-    };
-    match file.write_all(value.as_bytes()) {
-        Ok(_) => {}
-        Err(err_msg) => {
-            error!(
-                ?err_msg,
-                "Failed to save content for key: {key} to kv store (write failed)"
-            );
-        }
-    };
+    /// An embedded SQLite database using the `kv_store` table
+    Sqlite,
 }
 
-pub async fn load_kv(key: &str) -> Option<String> {
-    let path = get_file_path(key)?;
-    match fs::read_to_string(path) {
-        Ok(content) => Some(content),
-        Err(err_msg) => {
-            error!(?err_msg, "Failed to get content for key: {key}");
-            None
-        }
-    }
+/// Creates the store selected in the config
+pub fn new_store(clap_config: &ClapConfig) -> anyhow::Result<Arc<dyn KvStore>> {
+    info!("Using {:?} storage backend", clap_config.storage_backend);
+    Ok(match clap_config.storage_backend {
+        StorageBackend::File => Arc::new(FileStore::new(FileStore::DEFAULT_FOLDER)),
+        StorageBackend::Sqlite => Arc::new(
+            SqliteStore::try_new(&clap_config.sqlite_path).with_context(|| {
+                format!(
+                    "failed to open sqlite database at {:?}",
+                    clap_config.sqlite_path
+                )
+            })?,
+        ),
+    })
 }
@@ -0,0 +1,133 @@
+//! Stores each key as a json file in a folder
+
+use std::{
+    fs,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use super::KvStore;
+
+const EXTENSION: &str = "json";
+
+#[derive(Debug)]
+pub struct FileStore {
+    folder: PathBuf,
+}
+
+impl FileStore {
+    pub const DEFAULT_FOLDER: &str = "KV";
+
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+        }
+    }
+
+    /// Returns the path for the key creating the folder if needed
+    fn get_file_path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(&self.folder).with_context(|| {
+            format!(
+                "failed to create parent directory for key value store: {:?}",
+                self.folder
+            )
+        })?;
+        let mut result = self.folder.join(key);
+        if !result.add_extension(EXTENSION) {
+            anyhow::bail!("unable to add json extension to path: {result:?}");
+        }
+        Ok(result)
+    }
+
+    /// Writes to a temporary file first and then renames it into place so that a crash part way
+    /// through a write cannot leave a partially written file behind
+    pub fn write_atomically(path: &Path, value: &str) -> anyhow::Result<()> {
+        let mut tmp_path = path.to_path_buf();
+        if !tmp_path.add_extension("tmp") {
+            anyhow::bail!("unable to add tmp extension to path: {tmp_path:?}");
+        }
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)
+            .with_context(|| format!("failed to create temporary file: {tmp_path:?}"))?;
+        file.write_all(value.as_bytes())
+            .with_context(|| format!("failed to write to temporary file: {tmp_path:?}"))?;
+        file.sync_all()
+            .with_context(|| format!("failed to sync temporary file: {tmp_path:?}"))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("failed to rename {tmp_path:?} to {path:?}"))?;
+        // The rename is only durable once the folder containing the file has been synced
+        #[cfg(unix)]
+        if let Some(parent) = path.parent() {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            fs::File::open(parent)
+                .and_then(|folder| folder.sync_all())
+                .with_context(|| format!("failed to sync folder: {parent:?}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl KvStore for FileStore {
+    fn save(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        let path = self.get_file_path(key)?;
+        Self::write_atomically(&path, value)
+            .with_context(|| format!("failed to save content for key: {key}"))
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let path = self.get_file_path(key)?;
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("failed to get content for key: {key} at {path:?}"))
+            }
+        }
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let path = self.get_file_path(key)?;
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("failed to delete key: {key} at {path:?}")),
+        }
+    }
+
+    fn keys(&self) -> anyhow::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.folder) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read kv store folder: {:?}", self.folder));
+            }
+        };
+        let mut result = Vec::new();
+        for entry in entries {
+            let entry = entry.context("failed to read entry in kv store folder")?;
+            let path = entry.path();
+            if !entry.file_type()?.is_file()
+                || path
+                    .extension()
+                    .is_none_or(|extension| extension != EXTENSION)
+            {
+                continue;
+            }
+            if let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) {
+                result.push(key.to_string());
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+}
@@ -0,0 +1,110 @@
+//! Keeps copies of stored values that could not be read so that they are not lost when the
+//! bot continues with the default value and later saves over the original key
+
+use std::fmt::Display;
+
+use anyhow::Context as _;
+use tracing::warn;
+
+use crate::{clock::Clock, model::schedule::UnixTimestamp, user_error::UserError};
+
+use super::KvStore;
+
+const PREFIX: &str = "quarantine.";
+
+pub fn is_quarantine_key(key: &str) -> bool {
+    key.starts_with(PREFIX)
+}
+
+/// A value that was moved aside because it failed to load
+#[derive(Debug)]
+pub struct QuarantinedEntry {
+    /// The key the copy is stored under (used to identify it for a restore)
+    pub name: String,
+    /// The key the value was originally stored under
+    pub original_key: String,
+    pub quarantined_at: UnixTimestamp,
+}
+
+impl QuarantinedEntry {
+    fn from_name(name: &str) -> Option<Self> {
+        let (original_key, timestamp) = name.strip_prefix(PREFIX)?.rsplit_once('.')?;
+        Some(Self {
+            name: name.to_string(),
+            original_key: original_key.to_string(),
+            quarantined_at: UnixTimestamp::new(timestamp.parse().ok()?),
+        })
+    }
+}
+
+/// Moves the content of `key` into a timestamped quarantine key and returns its name
+pub fn quarantine(
+    store: &dyn KvStore,
+    clock: &dyn Clock,
+    key: &str,
+    content: &str,
+) -> anyhow::Result<String> {
+    let timestamp = clock.now()?;
+    let name = format!("{PREFIX}{key}.{}", timestamp.0);
+    store
+        .save(&name, content)
+        .context("failed to save quarantine copy")?;
+    store
+        .delete(key)
+        .context("failed to remove original after making quarantine copy")?;
+    warn!("Content of key: {key} moved to quarantine as {name}");
+    Ok(name)
+}
+
+/// Lists the quarantined entries (oldest first)
+pub fn list(store: &dyn KvStore) -> anyhow::Result<Vec<QuarantinedEntry>> {
+    let mut result: Vec<QuarantinedEntry> = store
+        .keys()?
+        .iter()
+        .filter_map(|key| QuarantinedEntry::from_name(key))
+        .collect();
+    result.sort_by_key(|entry| entry.quarantined_at.0);
+    Ok(result)
+}
+
+/// Returns the entry and its content
+pub fn get(store: &dyn KvStore, name: &str) -> anyhow::Result<(QuarantinedEntry, String)> {
+    let entry = QuarantinedEntry::from_name(name).ok_or_else(|| {
+        UserError::new(format!("{name:?} is not the name of a quarantined entry"))
+    })?;
+    let content = store
+        .load(name)?
+        .ok_or_else(|| UserError::new(format!("no quarantined entry found named {name:?}")))?;
+    Ok((entry, content))
+}
+
+impl Display for QuarantinedEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` (from `{}` at {})",
+            self.name, self.original_key, self.quarantined_at
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{clock::TestClock, db::SqliteStore};
+
+    use super::*;
+
+    #[test]
+    fn named_after_the_clock() {
+        let store = SqliteStore::in_memory();
+        let clock = TestClock::new(UnixTimestamp::new(1_893_456_000));
+        store.save("ideas", "not json").unwrap();
+        let name = quarantine(&store, &clock, "ideas", "not json").unwrap();
+        assert_eq!(name, "quarantine.ideas.1893456000");
+        assert_eq!(store.load("ideas").unwrap(), None);
+        let entries = list(&store).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original_key, "ideas");
+        assert_eq!(entries[0].quarantined_at.0, 1_893_456_000);
+    }
+}
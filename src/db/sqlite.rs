@@ -0,0 +1,127 @@
+//! Stores the keys in the `kv_store` table of an embedded SQLite database
+
+use std::{path::Path, sync::Mutex};
+
+use anyhow::Context as _;
+use rusqlite::{Connection, OptionalExtension as _};
+use tracing::info;
+
+use super::KvStore;
+
+/// Applied in order. The (1 based) number of the last migration run is stored in `user_version`
+const MIGRATIONS: [&str; 1] = [include_str!(
+    "../../migrations_sqlite/20250114063231_kv_store.sql"
+)];
+
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn try_new(path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open(path).context("failed to open connection")?;
+        Self::try_from_connection(connection)
+    }
+
+    /// A database that only lives as long as the store (for tests)
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self::try_from_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn try_from_connection(mut connection: Connection) -> anyhow::Result<Self> {
+        run_migrations(&mut connection).context("failed to run migrations")?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn guard_connection(&self) -> anyhow::Result<std::sync::MutexGuard<'_, Connection>> {
+        match self.connection.lock() {
+            Ok(guard) => Ok(guard),
+            Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+        }
+    }
+}
+
+fn run_migrations(connection: &mut Connection) -> anyhow::Result<()> {
+    let current: u32 = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .context("failed to read user_version")?;
+    for (version, migration) in (1u32..).zip(MIGRATIONS).skip(current as usize) {
+        info!("Running sqlite migration #{version}");
+        let tx = connection.transaction()?;
+        tx.execute_batch(migration)
+            .with_context(|| format!("migration #{version} failed"))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+impl KvStore for SqliteStore {
+    fn save(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.guard_connection()?
+            .execute(
+                "INSERT INTO kv_store (id, content)
+                VALUES (?1, ?2)
+                ON CONFLICT(id)
+                DO UPDATE SET
+                content = excluded.content;",
+                (key, value),
+            )
+            .with_context(|| format!("failed to save content for key: {key}"))?;
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.guard_connection()?
+            .query_row("SELECT content FROM kv_store WHERE id = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+            .with_context(|| format!("failed to get content for key: {key}"))
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.guard_connection()?
+            .execute("DELETE FROM kv_store WHERE id = ?1", [key])
+            .with_context(|| format!("failed to delete key: {key}"))?;
+        Ok(())
+    }
+
+    fn keys(&self) -> anyhow::Result<Vec<String>> {
+        let guard = self.guard_connection()?;
+        let mut statement = guard.prepare("SELECT id FROM kv_store ORDER BY id")?;
+        let result = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()
+            .context("failed to list keys")?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_then_load() {
+        let store = SqliteStore::in_memory();
+        assert_eq!(store.load("ideas").unwrap(), None);
+        store.save("ideas", "first").unwrap();
+        store.save("ideas", "second").unwrap();
+        assert_eq!(store.load("ideas").unwrap().as_deref(), Some("second"));
+        assert_eq!(store.keys().unwrap(), vec!["ideas".to_string()]);
+        store.delete("ideas").unwrap();
+        assert_eq!(store.load("ideas").unwrap(), None);
+    }
+
+    #[test]
+    fn migrations_only_run_once() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        run_migrations(&mut connection).unwrap();
+        run_migrations(&mut connection).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+//! Funnels all saves through one background writer per key.
+//!
+//! Saves for the same key are applied in the order they were requested and if more saves come in
+//! while a write is in progress only the latest value gets written (older ones are superseded)
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::metrics::KvMetrics;
+
+use super::KvStore;
+
+/// How long the writer waits after being woken before it writes to give bursts of saves a chance to be combined
+const COALESCE_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct KvWriter {
+    store: Arc<dyn KvStore>,
+    keys: Mutex<HashMap<String, KeyWriter>>,
+    metrics: KvMetrics,
+}
+
+#[derive(Debug)]
+struct KeyWriter {
+    /// The latest value requested to be saved
+    pending: watch::Sender<Pending>,
+    /// Updated by the writer after each write attempt
+    written: watch::Receiver<Written>,
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    seq: u64,
+    value: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Written {
+    seq: u64,
+    /// Set if the write for this `seq` failed
+    error: Option<String>,
+}
+
+/// Returned for each save so the caller can wait until the value (or a newer one) has been written
+#[derive(Debug)]
+pub struct PendingSave {
+    key: String,
+    seq: u64,
+    written: watch::Receiver<Written>,
+}
+
+impl KvWriter {
+    pub fn new(store: Arc<dyn KvStore>, metrics: KvMetrics) -> Self {
+        Self {
+            store,
+            keys: Default::default(),
+            metrics,
+        }
+    }
+
+    pub fn store(&self) -> &dyn KvStore {
+        self.store.as_ref()
+    }
+
+    /// Queues the value to be written by the writer for this key (starting the writer if needed)
+    pub fn save(&self, key: &str, value: String) -> anyhow::Result<PendingSave> {
+        let mut guard = match self.keys.lock() {
+            Ok(guard) => guard,
+            Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+        };
+        let key_writer = guard.entry(key.to_string()).or_insert_with(|| {
+            KeyWriter::spawn(
+                key.to_string(),
+                Arc::clone(&self.store),
+                self.metrics.clone(),
+            )
+        });
+        let mut seq = 0;
+        key_writer.pending.send_modify(|pending| {
+            pending.seq += 1;
+            pending.value = Some(value);
+            seq = pending.seq;
+        });
+        Ok(PendingSave {
+            key: key.to_string(),
+            seq,
+            written: key_writer.written.clone(),
+        })
+    }
+
+    /// Waits for all saves requested before this call to be written
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let pending_saves: Vec<PendingSave> = {
+            let guard = match self.keys.lock() {
+                Ok(guard) => guard,
+                Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+            };
+            guard
+                .iter()
+                .map(|(key, key_writer)| PendingSave {
+                    key: key.clone(),
+                    seq: key_writer.pending.borrow().seq,
+                    written: key_writer.written.clone(),
+                })
+                .collect()
+        };
+        let mut errors = Vec::new();
+        for pending_save in pending_saves {
+            if let Err(e) = pending_save.flushed().await {
+                errors.push(format!("{e:#}"));
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("failed to flush all keys: {}", errors.join("; "));
+        }
+        Ok(())
+    }
+}
+
+impl KeyWriter {
+    fn spawn(key: String, store: Arc<dyn KvStore>, metrics: KvMetrics) -> Self {
+        let (pending, mut pending_rx) = watch::channel(Pending::default());
+        let (written_tx, written) = watch::channel(Written::default());
+        tokio::spawn(async move {
+            info!("Writer started for key: {key}");
+            while pending_rx.changed().await.is_ok() {
+                tokio::time::sleep(COALESCE_DELAY).await;
+                let (seq, value) = {
+                    let pending = pending_rx.borrow_and_update();
+                    (pending.seq, pending.value.clone())
+                };
+                let Some(value) = value else {
+                    continue;
+                };
+                let store = Arc::clone(&store);
+                let key_for_save = key.clone();
+                let started = Instant::now();
+                let error =
+                    match tokio::task::spawn_blocking(move || store.save(&key_for_save, &value))
+                        .await
+                    {
+                        Ok(Ok(())) => None,
+                        Ok(Err(err_msg)) => {
+                            error!(
+                                ?err_msg,
+                                "Failed to save content for key: {key} to kv store"
+                            );
+                            Some(format!("{err_msg:#}"))
+                        }
+                        Err(err_msg) => {
+                            error!(?err_msg, "Save task for key: {key} did not complete");
+                            Some(err_msg.to_string())
+                        }
+                    };
+                metrics.record_save(started.elapsed(), error.is_none());
+                written_tx.send_replace(Written { seq, error });
+            }
+            warn!("Writer stopped for key: {key}");
+        });
+        Self { pending, written }
+    }
+}
+
+impl PendingSave {
+    /// Resolves once this save (or a later one for the same key) has been written
+    pub async fn flushed(mut self) -> anyhow::Result<()> {
+        let written = match self.written.wait_for(|x| x.seq >= self.seq).await {
+            Ok(written) => written.clone(),
+            Err(_) => anyhow::bail!("writer for key: {} stopped before saving", self.key),
+        };
+        match written.error {
+            Some(e) => anyhow::bail!("failed to save key: {}. {e}", self.key),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every write so the tests can check what actually reached the store
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        writes: Mutex<Vec<(String, String)>>,
+    }
+
+    impl KvStore for RecordingStore {
+        fn save(&self, key: &str, value: &str) -> anyhow::Result<()> {
+            self.writes
+                .lock()
+                .unwrap()
+                .push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+
+        fn load(&self, key: &str) -> anyhow::Result<Option<String>> {
+            Ok(self
+                .writes
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .find_map(|(k, v)| (k == key).then(|| v.clone())))
+        }
+
+        fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.writes.lock().unwrap().retain(|(k, _)| k != key);
+            Ok(())
+        }
+
+        fn keys(&self) -> anyhow::Result<Vec<String>> {
+            let mut result: Vec<String> = self
+                .writes
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(k, _)| k.clone())
+                .collect();
+            result.sort();
+            result.dedup();
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn burst_is_coalesced_and_latest_wins() {
+        let store = Arc::new(RecordingStore::default());
+        let writer = KvWriter::new(store.clone(), KvMetrics::default());
+        let mut last = None;
+        for i in 0..50 {
+            last = Some(writer.save("ideas", i.to_string()).unwrap());
+        }
+        last.unwrap().flushed().await.unwrap();
+        assert_eq!(store.load("ideas").unwrap().as_deref(), Some("49"));
+        assert!(store.writes.lock().unwrap().len() < 50);
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_all_keys() {
+        let store = Arc::new(RecordingStore::default());
+        let writer = KvWriter::new(store.clone(), KvMetrics::default());
+        writer.save("ideas", "a".to_string()).unwrap();
+        writer.save("scores", "b".to_string()).unwrap();
+        writer.flush().await.unwrap();
+        assert_eq!(store.load("ideas").unwrap().as_deref(), Some("a"));
+        assert_eq!(store.load("scores").unwrap().as_deref(), Some("b"));
+    }
+}
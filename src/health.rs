@@ -0,0 +1,204 @@
+//! Optional HTTP endpoints so the host can monitor the bot (eg. `curl localhost:8080/readyz`)
+//!
+//! - `/healthz` the process is running
+//! - `/readyz` connected to Discord, the data is loaded and the heartbeat is recent
+//! - `/version` the version of the bot
+//! - `/metrics` see [`crate::metrics`]
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicI64, Ordering},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use axum::{
+    Router,
+    extract::State,
+    http::{StatusCode, header},
+    routing::get,
+};
+use human_time::ToHumanTimeString as _;
+use poise::serenity_prelude::{ConnectionStage, FullEvent};
+use tracing::{error, info, instrument};
+
+use crate::{SharedConfig, heartbeat, model::schedule::UnixTimestamp};
+
+/// What the bot reports about itself to feed the readiness check
+#[derive(Debug, Default)]
+pub struct HealthState {
+    gateway_connected: AtomicBool,
+    data_loaded: AtomicBool,
+    shutting_down: AtomicBool,
+    /// The last heartbeat saved by this process (0 if there has not been one yet)
+    last_heartbeat: AtomicI64,
+}
+
+impl HealthState {
+    /// If the last heartbeat is older than this the heartbeat has stopped
+    const HEARTBEAT_MAX_AGE: Duration = heartbeat::INTERVAL.saturating_mul(2);
+
+    pub fn set_gateway_connected(&self, value: bool) {
+        self.gateway_connected.store(value, Ordering::Relaxed);
+    }
+
+    pub fn set_data_loaded(&self) {
+        self.data_loaded.store(true, Ordering::Relaxed);
+    }
+
+    /// Once set commands are refused and the bot reports that it is not ready
+    pub fn set_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+
+    pub fn record_heartbeat(&self, timestamp: UnixTimestamp) {
+        self.last_heartbeat.store(timestamp.0, Ordering::Relaxed);
+    }
+
+    /// Keeps track of the connection to Discord
+    pub fn handle_event(&self, event: &FullEvent) {
+        match event {
+            FullEvent::Ready { .. } | FullEvent::Resume { .. } => self.set_gateway_connected(true),
+            FullEvent::ShardStageUpdate { event } => {
+                info!(?event.old, ?event.new, "Shard stage changed");
+                self.set_gateway_connected(matches!(event.new, ConnectionStage::Connected));
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the reasons the bot is not ready (empty if it is ready)
+    fn not_ready_reasons(&self, now: UnixTimestamp) -> Vec<&'static str> {
+        let mut result = Vec::new();
+        if self.is_shutting_down() {
+            result.push("shutting down");
+        }
+        if !self.gateway_connected.load(Ordering::Relaxed) {
+            result.push("not connected to Discord");
+        }
+        if !self.data_loaded.load(Ordering::Relaxed) {
+            result.push("data not loaded");
+        }
+        let last_heartbeat = UnixTimestamp::new(self.last_heartbeat.load(Ordering::Relaxed));
+        if last_heartbeat.0 == 0 {
+            result.push("heartbeat not started");
+        } else if now
+            .duration_since(last_heartbeat)
+            .is_some_and(|age| age > Self::HEARTBEAT_MAX_AGE)
+        {
+            result.push("heartbeat is stale");
+        }
+        result
+    }
+}
+
+/// Starts listening if a port was configured. Fails if unable to listen on the port
+pub async fn start_health_server(shared_config: &'static SharedConfig) -> anyhow::Result<()> {
+    let Some(address) = shared_config.health_address else {
+        info!("Health endpoints disabled as no port was set");
+        return Ok(());
+    };
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .with_context(|| format!("failed to listen for health checks on {address}"))?;
+    info!("Health endpoints listening on http://{address}");
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .route("/metrics", get(metrics))
+        .with_state(shared_config);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            error!(?err, "health endpoints stopped");
+        }
+    });
+    Ok(())
+}
+
+#[instrument(skip(shared_config))]
+async fn healthz(State(shared_config): State<&'static SharedConfig>) -> String {
+    format!(
+        "OK\nUptime: {}",
+        shared_config.start_instant.elapsed().to_human_time_string()
+    )
+}
+
+#[instrument(skip(shared_config))]
+async fn readyz(State(shared_config): State<&'static SharedConfig>) -> (StatusCode, String) {
+    let reasons = match shared_config.clock.now() {
+        Ok(now) => shared_config.health.not_ready_reasons(now),
+        Err(err) => {
+            error!(?err, "failed to get the time for readiness check");
+            vec!["unable to get the current time"]
+        }
+    };
+    if reasons.is_empty() {
+        (StatusCode::OK, "READY".to_string())
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("NOT READY\n- {}", reasons.join("\n- ")),
+        )
+    }
+}
+
+async fn version() -> &'static str {
+    version::version!()
+}
+
+async fn metrics(
+    State(shared_config): State<&'static SharedConfig>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), (StatusCode, String)> {
+    match shared_config.metrics.encode() {
+        Ok(body) => Ok((
+            [(
+                header::CONTENT_TYPE,
+                "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            )],
+            body,
+        )),
+        Err(err) => {
+            error!(?err, "failed to encode metrics");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const NOW: i64 = 1_893_456_000;
+
+    #[rstest]
+    #[case::ready(true, true, Some(NOW - 60), false, vec![])]
+    #[case::starting(false, false, None, false, vec!["not connected to Discord", "data not loaded", "heartbeat not started"])]
+    #[case::stale_heartbeat(true, true, Some(NOW - 3600), false, vec!["heartbeat is stale"])]
+    #[case::shutting_down(true, true, Some(NOW - 60), true, vec!["shutting down"])]
+    fn readiness(
+        #[case] connected: bool,
+        #[case] data_loaded: bool,
+        #[case] last_heartbeat: Option<i64>,
+        #[case] shutting_down: bool,
+        #[case] expected: Vec<&str>,
+    ) {
+        let state = HealthState::default();
+        if shutting_down {
+            state.set_shutting_down();
+        }
+        state.set_gateway_connected(connected);
+        if data_loaded {
+            state.set_data_loaded();
+        }
+        if let Some(last_heartbeat) = last_heartbeat {
+            state.record_heartbeat(UnixTimestamp::new(last_heartbeat));
+        }
+        assert_eq!(state.not_ready_reasons(UnixTimestamp::new(NOW)), expected);
+    }
+}
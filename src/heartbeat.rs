@@ -1,64 +1,199 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use crate::{
-    db::{load_kv, save_kv},
-    model::schedule::UnixTimestamp,
-};
+use crate::{SharedConfig, clock::Clock, model::schedule::UnixTimestamp};
 use human_time::ToHumanTimeString;
+use tokio::task::JoinHandle;
 use tracing::{error, info};
 
-const KEY: &str = "HEARTBEAT";
-
-pub fn start_heartbeat() {
-    tokio::spawn(async move {
-        info!("Heartbeat started");
-        loop {
-            let timestamp = match UnixTimestamp::now() {
-                Ok(x) => x,
-                Err(err) => {
-                    error!(?err, "failed to get timestamp heartbeat stopping");
-                    break;
-                }
-            };
-            save_kv(KEY, timestamp.to_db_fmt()).await;
-            tokio::time::sleep(std::time::Duration::from_secs(600)).await;
-        }
-    });
+use self::downtime::{Downtime, DowntimeHistory};
+
+pub mod downtime;
+
+pub const KEY: &str = "HEARTBEAT";
+/// The time of the last clean shutdown. If it matches the last heartbeat the bot was stopped on
+/// purpose
+pub const CLEAN_SHUTDOWN_KEY: &str = "CLEAN_SHUTDOWN";
+
+/// How often the heartbeat is saved
+pub(crate) const INTERVAL: Duration = Duration::from_secs(600);
+
+/// Returns the handle of the background task so it can be stopped when shutting down
+pub fn start_heartbeat(shared_config: &'static SharedConfig) -> JoinHandle<()> {
+    tokio::spawn(run_heartbeat(
+        Arc::clone(&shared_config.clock),
+        move |timestamp| {
+            shared_config.health.record_heartbeat(timestamp);
+            shared_config.save_kv(KEY, &timestamp).map(|_| ())
+        },
+    ))
 }
 
-pub async fn last_heartbeat_info() -> String {
-    match load_kv(KEY).await {
-        Some(db_value) => match UnixTimestamp::from_db_fmt(&db_value) {
-            Ok(last_heartbeat) => {
-                let Ok(now) = UnixTimestamp::now() else {
-                    return format!(
-                        "Last Heartbeat: {last_heartbeat} but Failed to get current timestamp"
-                    );
-                };
-                let seconds_since_last_heartbeat = now.0 - last_heartbeat.0;
-                if seconds_since_last_heartbeat < 0 {
-                    return format!(
-                        "Last heartbeat in the future?! Last heartbeat: {last_heartbeat}, Now: {now}"
-                    );
-                }
-                let Ok(seconds_since_last_heartbeat) = seconds_since_last_heartbeat.try_into()
-                else {
-                    // Invalid u64
-                    return format!(
-                        "Invalid u64!!! Seconds since heartbeat: {seconds_since_last_heartbeat},  Last heartbeat: {last_heartbeat}, Now: {now}"
-                    );
-                };
-                let downtime = Duration::from_secs(seconds_since_last_heartbeat);
-                format!(
-                    "Downtime: {}\nLast Heartbeat: {last_heartbeat}\nNow: {now}",
-                    downtime.to_human_time_string()
-                )
-            }
+/// Saves the current time every [`INTERVAL`] until the clock fails
+async fn run_heartbeat(
+    clock: Arc<dyn Clock>,
+    mut save: impl FnMut(UnixTimestamp) -> anyhow::Result<()>,
+) {
+    info!("Heartbeat started");
+    loop {
+        let timestamp = match clock.now() {
+            Ok(x) => x,
             Err(err) => {
-                error!(?err);
-                "Error Loading Last Heartbeat".to_string()
+                error!(?err, "failed to get timestamp heartbeat stopping");
+                break;
             }
-        },
-        None => "First run".to_string(),
+        };
+        if let Err(err) = save(timestamp) {
+            error!(?err, "failed to save heartbeat");
+        }
+        clock.sleep(INTERVAL).await;
+    }
+}
+
+/// Adds the time from the last heartbeat until now to the downtime history. Must be called before
+/// the heartbeat is started as that replaces the last heartbeat
+pub async fn record_downtime(shared_config: &SharedConfig) -> anyhow::Result<()> {
+    let Some(last_heartbeat) = shared_config.load_kv::<UnixTimestamp>(KEY).await? else {
+        info!("No previous heartbeat so no downtime to record");
+        return Ok(());
+    };
+    let mut history: DowntimeHistory = shared_config
+        .load_or_default_kv(DowntimeHistory::DATA_KEY)
+        .await?;
+    history.push(Downtime {
+        start: last_heartbeat,
+        end: shared_config.clock.now()?,
+    });
+    shared_config.save_kv(DowntimeHistory::DATA_KEY, &history)?;
+    Ok(())
+}
+
+/// Saves the same time as both the heartbeat and the clean shutdown marker so the next start can
+/// tell that the bot was stopped on purpose
+pub fn record_clean_shutdown(shared_config: &SharedConfig) -> anyhow::Result<()> {
+    let now = shared_config.clock.now()?;
+    shared_config.save_kv(KEY, &now)?;
+    shared_config.save_kv(CLEAN_SHUTDOWN_KEY, &now)?;
+    Ok(())
+}
+
+/// Describes how the bot stopped given the last heartbeat and the last clean shutdown
+fn shutdown_kind(
+    last_heartbeat: UnixTimestamp,
+    clean_shutdown: Option<UnixTimestamp>,
+) -> &'static str {
+    if clean_shutdown == Some(last_heartbeat) {
+        "Planned (clean shutdown)"
+    } else {
+        "Unexpected (crashed or was killed)"
+    }
+}
+
+pub async fn last_heartbeat_info(shared_config: &SharedConfig) -> String {
+    match shared_config.load_kv::<UnixTimestamp>(KEY).await {
+        Ok(Some(last_heartbeat)) => {
+            let Ok(now) = shared_config.clock.now() else {
+                return format!(
+                    "Last Heartbeat: {last_heartbeat} but Failed to get current timestamp"
+                );
+            };
+            let Some(downtime) = now.duration_since(last_heartbeat) else {
+                return format!(
+                    "Last heartbeat in the future?! Last heartbeat: {last_heartbeat}, Now: {now}"
+                );
+            };
+            let clean_shutdown = shared_config
+                .load_kv::<UnixTimestamp>(CLEAN_SHUTDOWN_KEY)
+                .await
+                .unwrap_or_else(|err| {
+                    error!(?err, "failed to load clean shutdown marker");
+                    None
+                });
+            let timezone = shared_config.alliance_timezone;
+            format!(
+                "Shutdown: {}\nDowntime: {}\nLast Heartbeat: {last_heartbeat} ({})\nNow: {now} ({})",
+                shutdown_kind(last_heartbeat, clean_shutdown),
+                downtime.to_human_time_string(),
+                last_heartbeat.format_in(timezone),
+                now.format_in(timezone)
+            )
+        }
+        Err(err) => {
+            error!(?err);
+            "Error Loading Last Heartbeat".to_string()
+        }
+        Ok(None) => "First run".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rstest::rstest;
+
+    use crate::clock::TestClock;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn saves_every_interval() {
+        const START: i64 = 1_893_456_000;
+        let clock = Arc::new(TestClock::new(UnixTimestamp::new(START)));
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let saved_clone = Arc::clone(&saved);
+        let handle = tokio::spawn(run_heartbeat(clock, move |timestamp| {
+            saved_clone.lock().unwrap().push(timestamp);
+            Ok(())
+        }));
+        tokio::time::sleep(INTERVAL * 2 + Duration::from_secs(1)).await;
+        handle.abort();
+        let interval = INTERVAL.as_secs() as i64;
+        let expected: Vec<_> = [START, START + interval, START + 2 * interval]
+            .into_iter()
+            .map(UnixTimestamp::new)
+            .collect();
+        assert_eq!(*saved.lock().unwrap(), expected);
+    }
+
+    /// A heartbeat saved after the clean shutdown marker would make the stop look like a crash so
+    /// once stopped the heartbeat must not save again however much time passes
+    #[tokio::test(start_paused = true)]
+    async fn no_heartbeat_after_stop() {
+        const START: i64 = 1_893_456_000;
+        let clock: Arc<dyn Clock> = Arc::new(TestClock::new(UnixTimestamp::new(START)));
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let saved_clone = Arc::clone(&saved);
+        let handle = tokio::spawn(run_heartbeat(Arc::clone(&clock), move |timestamp| {
+            saved_clone.lock().unwrap().push(timestamp);
+            Ok(())
+        }));
+        tokio::time::sleep(INTERVAL + Duration::from_secs(1)).await;
+        let saved_before_stop = saved.lock().unwrap().clone();
+        assert_eq!(saved_before_stop.len(), 2);
+
+        crate::shutdown::stop_background_tasks(vec![handle]).await;
+        tokio::time::advance(INTERVAL).await;
+        tokio::time::sleep(INTERVAL * 3).await;
+
+        let last_saved = *saved_before_stop.last().unwrap();
+        assert!(
+            clock.now().unwrap().0 > last_saved.0 + 4 * INTERVAL.as_secs() as i64,
+            "clock did not advance so a late heartbeat could not be told apart"
+        );
+        assert_eq!(*saved.lock().unwrap(), saved_before_stop);
+    }
+
+    #[rstest]
+    #[case::clean(Some(100), "Planned (clean shutdown)")]
+    #[case::never_clean(None, "Unexpected (crashed or was killed)")]
+    #[case::heartbeat_after_clean(Some(40), "Unexpected (crashed or was killed)")]
+    fn describes_shutdown(#[case] clean_shutdown: Option<i64>, #[case] expected: &str) {
+        assert_eq!(
+            shutdown_kind(
+                UnixTimestamp::new(100),
+                clean_shutdown.map(UnixTimestamp::new)
+            ),
+            expected
+        );
     }
 }
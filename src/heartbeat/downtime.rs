@@ -0,0 +1,148 @@
+//! Keeps a record of each time the bot was down (from the last heartbeat before it stopped until
+//! it connected again)
+
+use std::{collections::VecDeque, fmt::Write as _, time::Duration};
+
+use human_time::ToHumanTimeString as _;
+
+use crate::model::{
+    schedule::UnixTimestamp,
+    versioned::{self, Upgrade, Versioned},
+};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default)]
+pub struct DowntimeHistory {
+    /// Oldest first
+    entries: VecDeque<Downtime>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Downtime {
+    /// The last heartbeat before the bot stopped (it may have run for up to one heartbeat
+    /// interval after this)
+    pub start: UnixTimestamp,
+    /// When the bot started again
+    pub end: UnixTimestamp,
+}
+
+impl DowntimeHistory {
+    pub const DATA_KEY: &'static str = "downtime_history";
+    pub const DISPLAY_TITLE: &'static str = "Downtime";
+
+    /// Once there are more entries than this the oldest are removed
+    const MAX_ENTRIES: usize = 500;
+    /// How many of the most recent outages are listed
+    const RECENT_TO_DISPLAY: usize = 10;
+    /// The periods (in days) that total downtime and availability are shown for
+    const SUMMARY_DAYS: [u64; 2] = [7, 30];
+
+    pub fn push(&mut self, downtime: Downtime) {
+        self.entries.push_back(downtime);
+        while self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    /// How much of the `period` leading up to `now` the bot was down for
+    pub fn total_within(&self, now: UnixTimestamp, period: Duration) -> Duration {
+        let period_start = now.0.saturating_sub_unsigned(period.as_secs());
+        let seconds: u64 = self
+            .entries
+            .iter()
+            .map(|downtime| {
+                let start = downtime.start.0.max(period_start);
+                let end = downtime.end.0.min(now.0);
+                end.saturating_sub(start).max(0).unsigned_abs()
+            })
+            .sum();
+        Duration::from_secs(seconds)
+    }
+
+    /// Lists the recent outages followed by the totals for each summary period
+    pub fn summary(&self, now: UnixTimestamp) -> anyhow::Result<String> {
+        let mut result = String::new();
+        if self.entries.is_empty() {
+            result.push_str("No downtime recorded\n");
+        } else {
+            result.push_str("Most recent outages:\n");
+        }
+        for downtime in self.entries.iter().rev().take(Self::RECENT_TO_DISPLAY) {
+            let length = downtime
+                .end
+                .duration_since(downtime.start)
+                .unwrap_or_default();
+            writeln!(
+                result,
+                "- {} to {} ({})",
+                downtime.start.discord_tag('f'),
+                downtime.end.discord_tag('f'),
+                length.to_human_time_string()
+            )?;
+        }
+        for days in Self::SUMMARY_DAYS {
+            let period = Duration::from_secs(days * 24 * 60 * 60);
+            let down = self.total_within(now, period);
+            let availability = 100.0 * (1.0 - down.as_secs_f64() / period.as_secs_f64());
+            write!(result, "\nLast {days} days: {availability:.2}% available")?;
+            if !down.is_zero() {
+                write!(result, " (down for {})", down.to_human_time_string())?;
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Versioned for DowntimeHistory {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const NOW: i64 = 1_893_456_000;
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    fn history() -> DowntimeHistory {
+        let mut result = DowntimeHistory::default();
+        for (start, end) in [
+            (-40 * DAY, -39 * DAY),
+            (-8 * DAY - HOUR, -8 * DAY + HOUR),
+            (-2 * HOUR, -HOUR),
+        ] {
+            result.push(Downtime {
+                start: UnixTimestamp::new(NOW + start),
+                end: UnixTimestamp::new(NOW + end),
+            });
+        }
+        result
+    }
+
+    #[rstest]
+    #[case::only_recent(1, HOUR)]
+    #[case::partly_overlapping(8, 2 * HOUR)]
+    #[case::excludes_older(30, 3 * HOUR)]
+    fn total_within(#[case] days: u64, #[case] expected_seconds: i64) {
+        let period = Duration::from_secs(days * DAY as u64);
+        assert_eq!(
+            history().total_within(UnixTimestamp::new(NOW), period),
+            Duration::from_secs(expected_seconds as u64)
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let summary = history().summary(UnixTimestamp::new(NOW)).unwrap();
+        assert!(
+            summary.contains("Last 7 days: 99.40% available"),
+            "{summary}"
+        );
+        assert!(
+            summary.contains("Last 30 days: 99.58% available"),
+            "{summary}"
+        );
+    }
+}
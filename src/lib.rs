@@ -9,6 +9,8 @@ mod used_in_bin {
     use tracing_subscriber as _;
 }
 
+use std::{net::IpAddr, num::NonZeroUsize, path::PathBuf};
+
 use secrecy::SecretString;
 
 use clap::Parser;
@@ -16,16 +18,24 @@ use clap::Parser;
 use tracing::{info, instrument};
 
 pub use self::{
-    commands::commands_list,
+    commands::{commands_list, on_error, record_command_success, refuse_during_shutdown},
     config::{SharedConfig, StartupConfig},
+    db::StorageBackend,
     model::Data,
 };
 
+pub mod backup;
+mod clock;
 mod commands;
 mod config;
 mod db;
+pub mod health;
 pub mod heartbeat;
+pub mod metrics;
 mod model;
+mod rate_limit;
+pub mod shutdown;
+mod user_error;
 
 /// Type used by poise framework as the context when commands are triggered
 type Context<'a> = poise::Context<'a, Data, anyhow::Error>;
@@ -109,4 +119,48 @@ pub struct ClapConfig {
     /// For bot status messages like on connection
     #[arg(long, env = "CHANNEL_BOT_STATUS_ID")]
     pub channel_bot_status_id: String,
+
+    /// Where the data is stored
+    #[arg(long, env = "STORAGE_BACKEND", value_enum, default_value_t)]
+    pub storage_backend: StorageBackend,
+
+    /// The database file to use (Only used by the sqlite storage backend)
+    #[arg(long, env = "SQLITE_PATH", default_value = "bazooka_bot.sqlite")]
+    pub sqlite_path: PathBuf,
+
+    /// Hours between automatic backups of the data (0 disables them)
+    #[arg(long, env = "BACKUP_INTERVAL_HOURS", default_value_t = 24)]
+    pub backup_interval_hours: u64,
+
+    /// How many backups to keep (the oldest are deleted first)
+    #[arg(long, env = "BACKUP_KEEP", default_value = "30")]
+    pub backup_keep: NonZeroUsize,
+
+    /// How many hours after an unranked event starts it can still be undone
+    #[arg(long, env = "UNDO_START_EVENT_GRACE_HOURS", default_value_t = 24)]
+    pub undo_start_event_grace_hours: u64,
+
+    /// Comma separated list of how many hours before a scheduled unranked start to post a
+    /// reminder (0 disables)
+    #[arg(
+        long,
+        env = "UNRANKED_REMINDER_HOURS",
+        value_delimiter = ',',
+        default_value = "24,1"
+    )]
+    pub unranked_reminder_hours: Vec<u64>,
+
+    /// The timezone the alliance uses (eg. "America/New_York") for showing times alongside
+    /// Discord's timestamps which each user sees in their own timezone
+    #[arg(long, env = "ALLIANCE_TIMEZONE", default_value = "UTC")]
+    pub alliance_timezone: chrono_tz::Tz,
+
+    /// Port for the HTTP health endpoints (`/healthz`, `/readyz`, `/version` and `/metrics`). They
+    /// are disabled if not set
+    #[arg(long, env = "HEALTH_PORT")]
+    pub health_port: Option<u16>,
+
+    /// The address the health endpoints listen on
+    #[arg(long, env = "HEALTH_HOST", default_value = "127.0.0.1")]
+    pub health_host: IpAddr,
 }
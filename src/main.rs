@@ -1,5 +1,8 @@
 use anyhow::{Context as _, bail};
-use bazooka_bot::{ClapConfig, Data, SharedConfig, StartupConfig, commands_list, heartbeat};
+use bazooka_bot::{
+    ClapConfig, Data, SharedConfig, StartupConfig, backup, commands_list, health, heartbeat,
+    metrics, on_error, record_command_success, refuse_during_shutdown, shutdown,
+};
 use poise::serenity_prelude::{ClientBuilder, GatewayIntents};
 use secrecy::ExposeSecret;
 use std::sync::Arc;
@@ -39,6 +42,7 @@ async fn main() -> anyhow::Result<()> {
     let shared_config =
         SharedConfig::try_new(&clap_config).context("failed to created shared_config")?;
     let discord_token = clap_config.discord_token;
+    health::start_health_server(shared_config).await?;
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -52,6 +56,15 @@ async fn main() -> anyhow::Result<()> {
                 case_insensitive_commands: true,
                 ..Default::default()
             },
+            command_check: Some(|ctx| Box::pin(refuse_during_shutdown(ctx))),
+            post_command: |ctx| Box::pin(record_command_success(ctx)),
+            on_error: |error| Box::pin(on_error(error)),
+            event_handler: |_ctx, event, _framework, data| {
+                Box::pin(async move {
+                    data.inner.shared_config.health.handle_event(event);
+                    Ok(())
+                })
+            },
             ..Default::default()
         })
         .setup(move |ctx, ready, framework| {
@@ -81,7 +94,7 @@ async fn main() -> anyhow::Result<()> {
                 let connect_msg = format!(
                     "{} is connected! Version: {}\n{}", 
                     ready.user.name, version!(),
-                    heartbeat::last_heartbeat_info().await,
+                    heartbeat::last_heartbeat_info(shared_config).await,
                 );
                 info!("{connect_msg}");
                 if let Some(channel) = shared_config.channel_bot_status{
@@ -89,8 +102,34 @@ async fn main() -> anyhow::Result<()> {
                 } else{
                     warn!("Not sending connection notification because channel_bot_status not set");
                 }
-                let data = Data::new(shared_config, ctx.clone()).await;
-                heartbeat::start_heartbeat();
+                shared_config.health.set_gateway_connected(true);
+                let data = Data::new(shared_config, ctx.clone())
+                    .await
+                    .context("failed to load data")?;
+                shared_config.health.set_data_loaded();
+                // Loads from the kv store so must run before the startup alerts are taken
+                if let Err(err) = heartbeat::record_downtime(shared_config).await {
+                    error!(?err, "failed to record downtime");
+                }
+                for alert in shared_config.take_startup_alerts() {
+                    if let Some(channel) = shared_config.channel_bot_status {
+                        channel.say(ctx, alert).await?;
+                    } else {
+                        warn!("Not sending startup alert because channel_bot_status not set");
+                    }
+                }
+                let mut background_tasks = vec![heartbeat::start_heartbeat(shared_config)];
+                background_tasks.extend(backup::start_periodic_backups(shared_config));
+                background_tasks.push(metrics::start_gateway_latency_sampling(
+                    shared_config,
+                    Arc::clone(framework.shard_manager()),
+                ));
+                shutdown::start_signal_handler(
+                    data.clone(),
+                    ctx.clone(),
+                    Arc::clone(framework.shard_manager()),
+                    background_tasks,
+                );
                 info!("END OF SETUP CLOSURE");
                 Ok(data)
             })
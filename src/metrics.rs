@@ -0,0 +1,252 @@
+//! Counters and histograms served in the Prometheus text format at `/metrics` on the health
+//! endpoints so that usage can be graphed over a season
+
+use std::{sync::Arc, time::Duration};
+
+use poise::serenity_prelude::ShardManager;
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{Histogram, exponential_buckets},
+    },
+    registry::Registry,
+};
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::{SharedConfig, model::schedule::Objective};
+
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    command_invocations: Family<CommandLabels, Counter>,
+    auth_denials: Family<AuthDenialLabels, Counter>,
+    kv: KvMetrics,
+    scheduled_task_runs: Family<TaskRunLabels, Counter>,
+    gateway_latency: Histogram,
+    ideas: Gauge,
+    scores: Gauge,
+}
+
+/// Kept separate so that the kv writer can have its own copy (both copies update the same values)
+#[derive(Debug, Clone)]
+pub struct KvMetrics {
+    save_duration: Histogram,
+    save_failures: Counter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EncodeLabelValue)]
+pub enum CommandOutcome {
+    Success,
+    Failed,
+    InvalidArguments,
+    /// Did not run (eg. failed a check like `is_auth`)
+    Refused,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct CommandLabels {
+    command: String,
+    outcome: CommandOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct AuthDenialLabels {
+    command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct TaskRunLabels {
+    objective: &'static str,
+    succeeded: bool,
+}
+
+impl Metrics {
+    /// How often the gateway latency is sampled
+    const GATEWAY_LATENCY_INTERVAL: Duration = Duration::from_secs(60);
+
+    pub fn kv(&self) -> &KvMetrics {
+        &self.kv
+    }
+
+    pub fn record_command(&self, command: &str, outcome: CommandOutcome) {
+        self.command_invocations
+            .get_or_create(&CommandLabels {
+                command: command.to_string(),
+                outcome,
+            })
+            .inc();
+    }
+
+    pub fn record_auth_denial(&self, command: &str) {
+        self.auth_denials
+            .get_or_create(&AuthDenialLabels {
+                command: command.to_string(),
+            })
+            .inc();
+    }
+
+    pub fn record_task_run(&self, objective: &Objective, succeeded: bool) {
+        self.scheduled_task_runs
+            .get_or_create(&TaskRunLabels {
+                objective: objective.kind(),
+                succeeded,
+            })
+            .inc();
+    }
+
+    pub fn set_idea_count(&self, count: usize) {
+        self.ideas.set(count as i64);
+    }
+
+    pub fn set_score_count(&self, count: usize) {
+        self.scores.set(count as i64);
+    }
+
+    /// Returns all the metrics in the text exposition format
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let mut result = String::new();
+        prometheus_client::encoding::text::encode(&mut result, &self.registry)?;
+        Ok(result)
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let command_invocations = Family::<CommandLabels, Counter>::default();
+        let auth_denials = Family::<AuthDenialLabels, Counter>::default();
+        let kv = KvMetrics::default();
+        let scheduled_task_runs = Family::<TaskRunLabels, Counter>::default();
+        // 12.5ms to 6.4s
+        let gateway_latency = Histogram::new(exponential_buckets(0.0125, 2.0, 10));
+        let ideas = Gauge::default();
+        let scores = Gauge::default();
+
+        let mut registry = Registry::with_prefix("bazooka");
+        registry.register(
+            "command_invocations",
+            "Commands run by qualified name and outcome",
+            command_invocations.clone(),
+        );
+        registry.register(
+            "auth_denials",
+            "Commands refused because the user did not have the auth role",
+            auth_denials.clone(),
+        );
+        registry.register(
+            "kv_save_duration_seconds",
+            "How long writes to the kv store took",
+            kv.save_duration.clone(),
+        );
+        registry.register(
+            "kv_save_failures",
+            "Writes to the kv store that failed",
+            kv.save_failures.clone(),
+        );
+        registry.register(
+            "scheduled_task_runs",
+            "Scheduled tasks run by objective and if they succeeded",
+            scheduled_task_runs.clone(),
+        );
+        registry.register(
+            "gateway_latency_seconds",
+            "Discord gateway heartbeat latency (sampled every minute)",
+            gateway_latency.clone(),
+        );
+        registry.register("ideas", "Current number of unranked ideas", ideas.clone());
+        registry.register(
+            "scores",
+            "Current number of unranked scores",
+            scores.clone(),
+        );
+
+        Self {
+            registry,
+            command_invocations,
+            auth_denials,
+            kv,
+            scheduled_task_runs,
+            gateway_latency,
+            ideas,
+            scores,
+        }
+    }
+}
+
+impl KvMetrics {
+    pub fn record_save(&self, duration: Duration, succeeded: bool) {
+        self.save_duration.observe(duration.as_secs_f64());
+        if !succeeded {
+            self.save_failures.inc();
+        }
+    }
+}
+
+impl Default for KvMetrics {
+    fn default() -> Self {
+        Self {
+            // 1ms to 8.2s
+            save_duration: Histogram::new(exponential_buckets(0.001, 2.0, 14)),
+            save_failures: Default::default(),
+        }
+    }
+}
+
+/// Records the latency of each shard in the background (the same value `ping` reports). Returns
+/// the handle of the background task so it can be stopped when shutting down
+pub fn start_gateway_latency_sampling(
+    shared_config: &'static SharedConfig,
+    shard_manager: Arc<ShardManager>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Gateway latency sampling started");
+        loop {
+            shared_config
+                .clock
+                .sleep(Metrics::GATEWAY_LATENCY_INTERVAL)
+                .await;
+            for runner in shard_manager.runners.lock().await.values() {
+                if let Some(latency) = runner.latency {
+                    shared_config
+                        .metrics
+                        .gateway_latency
+                        .observe(latency.as_secs_f64());
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_recorded_values() {
+        let metrics = Metrics::default();
+        metrics.record_command("unranked idea add", CommandOutcome::Success);
+        metrics.record_command("unranked idea add", CommandOutcome::Success);
+        metrics.record_auth_denial("admin reset");
+        metrics.record_task_run(&Objective::UnrankedStartEvent, false);
+        metrics.kv().record_save(Duration::from_millis(3), false);
+        metrics.set_idea_count(4);
+        let actual = metrics.encode().unwrap();
+        for expected in [
+            r#"bazooka_command_invocations_total{command="unranked idea add",outcome="Success"} 2"#,
+            r#"bazooka_auth_denials_total{command="admin reset"} 1"#,
+            r#"bazooka_scheduled_task_runs_total{objective="UnrankedStartEvent",succeeded="false"} 1"#,
+            "bazooka_kv_save_duration_seconds_count 1",
+            "bazooka_kv_save_failures_total 1",
+            "bazooka_ideas 4",
+            "bazooka_scores 0",
+        ] {
+            assert!(
+                actual.contains(expected),
+                "{expected} missing from:\n{actual}"
+            );
+        }
+    }
+}
@@ -10,14 +10,27 @@
 
 use std::sync::{Arc, Mutex};
 
-use crate::config::SharedConfig;
+use anyhow::{Context as _, bail};
+use tracing::info;
 
-use self::{schedule::ScheduledTasks, unranked::Unranked};
+use crate::{
+    config::SharedConfig,
+    db::PendingSave,
+    heartbeat::{self, downtime::DowntimeHistory},
+};
+
+use self::{
+    schedule::{ScheduledTasks, UnixTimestamp, history::ExecutionHistory},
+    unranked::{Unranked, ideas::Ideas, scores::Scores, start_event_undo::StartEventUndo},
+    versioned::{Versioned, from_json},
+};
 
 pub mod one_based_id;
 pub mod schedule;
+pub mod snapshot;
 pub mod unranked;
 pub mod user_serde;
+pub mod versioned;
 
 /// User data, which is stored and accessible in all command invocations, cheap to clone uses an Arc
 #[derive(Clone)]
@@ -29,6 +42,7 @@ pub struct DataInner {
     pub unranked: Unranked,
     pub ctx: poise::serenity_prelude::Context,
     pub schedule_tasks: Arc<Mutex<ScheduledTasks>>,
+    pub schedule_history: Arc<Mutex<ExecutionHistory>>,
     pub shared_config: &'static SharedConfig,
 }
 
@@ -36,20 +50,146 @@ impl Data {
     pub async fn new(
         shared_config: &'static SharedConfig,
         ctx: poise::serenity_prelude::Context,
-    ) -> Self {
+    ) -> anyhow::Result<Self> {
         let result = Data {
             inner: Arc::new(DataInner {
-                unranked: Unranked::new(shared_config).await,
+                unranked: Unranked::new(shared_config).await?,
                 shared_config,
-                schedule_tasks: Arc::new(Mutex::new(ScheduledTasks::new(shared_config).await)),
+                schedule_tasks: Arc::new(Mutex::new(ScheduledTasks::new(shared_config).await?)),
+                schedule_history: Arc::new(Mutex::new(ExecutionHistory::new(shared_config).await?)),
                 ctx,
             }),
         };
-        result.schedule_hydrate();
-        result
+        let missed = result.schedule_hydrate();
+        if !missed.is_empty() {
+            shared_config.add_startup_alert(format!(
+                "⚠️ Scheduled tasks were due while the bot was not running:\n{}\n{}",
+                missed.join("\n"),
+                heartbeat::last_heartbeat_info(shared_config).await
+            ));
+        }
+        Ok(result)
     }
 
-    fn save<T: serde::Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> {
+    fn save<T: Versioned>(&self, key: &str, value: &T) -> anyhow::Result<PendingSave> {
         self.inner.shared_config.save_kv(key, value)
     }
+
+    /// Replaces the value for `key` (both in memory and in storage) with the one in `content`.
+    /// Returns a value that can be used to wait until the new value has been written and any
+    /// messages for the caller (eg. scheduled tasks that were already due)
+    pub fn replace_kv_from_json(
+        &self,
+        key: &str,
+        content: &str,
+    ) -> anyhow::Result<(PendingSave, Vec<String>)> {
+        info!("Replacing value for key: {key}");
+        let replacement = KvReplacement::parse(key, content)?;
+        let key = replacement.key();
+        let pending_save = match replacement {
+            KvReplacement::Ideas(ideas) => self.inner.unranked.ideas_replace(ideas)?,
+            KvReplacement::Scores(scores) => self.inner.unranked.scores_replace(scores)?,
+            KvReplacement::StartEventUndo(undo) => {
+                self.inner.unranked.start_event_undo_replace(undo)?
+            }
+            KvReplacement::ScheduledTasks(tasks) => return self.schedule_replace(tasks),
+            KvReplacement::ScheduleHistory(history) => self.schedule_history_replace(history)?,
+            // Only kept in storage so there is nothing in memory to update
+            KvReplacement::Heartbeat(value) | KvReplacement::CleanShutdown(value) => {
+                self.save(key, &value)?
+            }
+            KvReplacement::DowntimeHistory(history) => self.save(key, &history)?,
+        };
+        Ok((pending_save, Vec::new()))
+    }
+}
+
+/// A value read from json for one of the keys that can be replaced
+enum KvReplacement {
+    Ideas(Ideas),
+    Scores(Scores),
+    StartEventUndo(StartEventUndo),
+    ScheduledTasks(ScheduledTasks),
+    ScheduleHistory(ExecutionHistory),
+    Heartbeat(UnixTimestamp),
+    CleanShutdown(UnixTimestamp),
+    DowntimeHistory(DowntimeHistory),
+}
+
+impl KvReplacement {
+    fn parse(key: &str, content: &str) -> anyhow::Result<Self> {
+        let context = || format!("failed to parse content for key: {key}");
+        Ok(match key {
+            Ideas::DATA_KEY => Self::Ideas(from_json(content).with_context(context)?),
+            Scores::DATA_KEY => Self::Scores(from_json(content).with_context(context)?),
+            StartEventUndo::DATA_KEY => {
+                Self::StartEventUndo(from_json(content).with_context(context)?)
+            }
+            ScheduledTasks::DATA_KEY => {
+                Self::ScheduledTasks(from_json(content).with_context(context)?)
+            }
+            ExecutionHistory::DATA_KEY => {
+                Self::ScheduleHistory(from_json(content).with_context(context)?)
+            }
+            heartbeat::KEY => Self::Heartbeat(from_json(content).with_context(context)?),
+            heartbeat::CLEAN_SHUTDOWN_KEY => {
+                Self::CleanShutdown(from_json(content).with_context(context)?)
+            }
+            DowntimeHistory::DATA_KEY => {
+                Self::DowntimeHistory(from_json(content).with_context(context)?)
+            }
+            _ => bail!("replacing the value for key: {key:?} is not supported"),
+        })
+    }
+
+    /// The key the value is stored under
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Ideas(_) => Ideas::DATA_KEY,
+            Self::Scores(_) => Scores::DATA_KEY,
+            Self::StartEventUndo(_) => StartEventUndo::DATA_KEY,
+            Self::ScheduledTasks(_) => ScheduledTasks::DATA_KEY,
+            Self::ScheduleHistory(_) => ExecutionHistory::DATA_KEY,
+            Self::Heartbeat(_) => heartbeat::KEY,
+            Self::CleanShutdown(_) => heartbeat::CLEAN_SHUTDOWN_KEY,
+            Self::DowntimeHistory(_) => DowntimeHistory::DATA_KEY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// The oldest version so that the upgrades are used too
+    fn fixture(key: &str) -> String {
+        let path = format!("{}/fixtures/{key}/v0.json", env!("CARGO_MANIFEST_DIR"));
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("missing fixture {path:?}: {e}"))
+    }
+
+    #[rstest]
+    #[case::ideas(Ideas::DATA_KEY, fixture(Ideas::DATA_KEY))]
+    #[case::scores(Scores::DATA_KEY, fixture(Scores::DATA_KEY))]
+    #[case::start_event_undo(StartEventUndo::DATA_KEY, fixture(StartEventUndo::DATA_KEY))]
+    #[case::scheduled_tasks(ScheduledTasks::DATA_KEY, fixture(ScheduledTasks::DATA_KEY))]
+    #[case::schedule_history(ExecutionHistory::DATA_KEY, fixture(ExecutionHistory::DATA_KEY))]
+    #[case::heartbeat(heartbeat::KEY, fixture(heartbeat::KEY))]
+    #[case::clean_shutdown(
+        heartbeat::CLEAN_SHUTDOWN_KEY,
+        versioned::to_json(&UnixTimestamp::new(1_893_456_000)).unwrap()
+    )]
+    #[case::downtime_history(DowntimeHistory::DATA_KEY, fixture(DowntimeHistory::DATA_KEY))]
+    fn replaces_each_key(#[case] key: &str, #[case] content: String) {
+        let actual = KvReplacement::parse(key, &content).unwrap();
+        assert_eq!(actual.key(), key);
+    }
+
+    #[rstest]
+    #[case::unknown_key("not_a_key", fixture(Ideas::DATA_KEY))]
+    #[case::wrong_type(Scores::DATA_KEY, fixture(heartbeat::KEY))]
+    fn rejects_replacement(#[case] key: &str, #[case] content: String) {
+        assert!(KvReplacement::parse(key, &content).is_err());
+    }
 }
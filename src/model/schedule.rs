@@ -1,21 +1,59 @@
-use super::one_based_id::OneBasedId;
-use crate::{Data, commands::do_start_event};
+use self::{
+    edit::TaskEdit,
+    history::{Execution, ExecutionHistory, ExecutionOutcome},
+    misfire::MisfirePolicy,
+    recurrence::Recurrence,
+    runner::TaskRunner,
+};
+use super::versioned::{self, Upgrade, Versioned};
+use crate::user_error::UserError;
 use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use human_time::ToHumanTimeString;
+use poise::serenity_prelude::{ChannelId, Mentionable as _};
+use serde_json::Value;
 use std::{
     fmt::Display,
+    num::NonZeroUsize,
     time::{Duration, UNIX_EPOCH},
 };
 use tokio::task::JoinHandle;
 use tracing::{error, info, instrument, warn};
 
+pub mod edit;
+pub mod history;
+pub mod misfire;
 pub mod protected_ops;
-pub type ScheduledTaskId = OneBasedId;
+pub mod recurrence;
+pub mod runner;
+pub mod time_input;
+
+/// Assigned when a task is created and never reused so it keeps referring to the same task
+/// even after other tasks complete or are cancelled
+#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(transparent)]
+pub struct ScheduledTaskId(NonZeroUsize);
+
+impl From<NonZeroUsize> for ScheduledTaskId {
+    fn from(value: NonZeroUsize) -> Self {
+        Self(value)
+    }
+}
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone, Copy)]
-pub struct UnixTimestamp(pub i32);
+impl Display for ScheduledTaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Seconds since the Unix epoch.
+///
+/// Stored as a plain number so values saved when this was an `i32` still load unchanged
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UnixTimestamp(pub i64);
 impl UnixTimestamp {
-    pub fn new(value: i32) -> Self {
+    pub fn new(value: i64) -> Self {
         Self(value)
     }
 
@@ -24,63 +62,182 @@ impl UnixTimestamp {
             .elapsed()
             .context("failed to get timestamp. System date before Unix Epoch?")?
             .as_secs();
-        let seconds_since_epoch: i32 = seconds_since_epoch
+        let seconds_since_epoch: i64 = seconds_since_epoch
             .try_into()
-            .context("failed to convert system time as seconds since epoch into i32")?;
+            .context("failed to convert system time as seconds since epoch into i64")?;
         Ok(Self(seconds_since_epoch))
     }
 
-    pub fn to_db_fmt(self) -> String {
-        self.0.to_string()
+    /// Number of seconds from `earlier` to `self` (negative if `earlier` is actually later)
+    pub fn seconds_since(&self, earlier: Self) -> i64 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Returns the time this is after `earlier` or `None` if it is not after it
+    pub fn duration_since(&self, earlier: Self) -> Option<Duration> {
+        u64::try_from(self.seconds_since(earlier))
+            .ok()
+            .map(Duration::from_secs)
+    }
+
+    pub fn to_date_time(self) -> anyhow::Result<DateTime<Utc>> {
+        DateTime::from_timestamp(self.0, 0)
+            .with_context(|| format!("timestamp {} out of range", self.0))
+    }
+
+    /// A Discord timestamp tag which each user sees in their own timezone.
+    /// See <https://discord.com/developers/docs/reference#message-formatting-timestamp-styles>
+    /// for the available styles
+    pub fn discord_tag(&self, style: char) -> String {
+        format!("<t:{}:{style}>", self.0)
+    }
+
+    /// Formats the timestamp as the date and time in `timezone` (eg. for the alliance's timezone)
+    pub fn format_in(&self, timezone: Tz) -> String {
+        match self.to_date_time() {
+            Ok(date_time) => date_time
+                .with_timezone(&timezone)
+                .format("%a, %d %b %Y %H:%M %Z")
+                .to_string(),
+            Err(_) => format!("{} (out of range)", self.0),
+        }
     }
+}
 
-    pub fn from_db_fmt(value: &str) -> anyhow::Result<Self> {
-        let value = value
-            .parse::<i32>()
-            .with_context(|| format!("failed to convert {value} into i32 for timestamp"))?;
-        Ok(Self(value))
+impl From<DateTime<Utc>> for UnixTimestamp {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self(value.timestamp())
     }
 }
 
 impl Display for UnixTimestamp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "<t:{0}:F> <t:{0}:R>", self.0)
+        write!(f, "{} {}", self.discord_tag('F'), self.discord_tag('R'))
     }
 }
 
+impl Versioned for UnixTimestamp {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
 /// Scheduled Tasks
 ///
 /// Stores the info needed to recreate these tasks if application is restarted
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 pub struct ScheduledTasks {
     data: Vec<ScheduledTask>,
+    /// The ID given to the most recently created task (0 if none have been created)
+    last_id: usize,
 }
 
 impl ScheduledTasks {
     pub const DISPLAY_TITLE: &'static str = "Scheduled Tasks";
-    pub async fn new(shared_config: &crate::SharedConfig) -> Self {
+    pub async fn new(shared_config: &crate::SharedConfig) -> anyhow::Result<Self> {
         shared_config.load_or_default_kv(Self::DATA_KEY).await
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Copy)]
+impl Versioned for ScheduledTasks {
+    const UPGRADES: &'static [Upgrade] = &[
+        versioned::from_unversioned,
+        add_recurrence,
+        add_task_ids,
+        add_misfire_policy,
+    ];
+}
+
+/// Version 2 added the optional recurrence rule (existing tasks only run once)
+fn add_recurrence(mut value: Value) -> anyhow::Result<Value> {
+    let tasks = value
+        .get_mut("data")
+        .and_then(Value::as_array_mut)
+        .context("expected a list of tasks")?;
+    for task in tasks {
+        task.as_object_mut()
+            .context("expected task to be an object")?
+            .insert("recurrence".to_string(), Value::Null);
+    }
+    Ok(value)
+}
+
+/// Version 3 gave each task a permanent ID (existing tasks are numbered in their current order
+/// so that their IDs stay the same as what was displayed before)
+fn add_task_ids(mut value: Value) -> anyhow::Result<Value> {
+    let map = value
+        .as_object_mut()
+        .context("expected scheduled tasks to be an object")?;
+    let tasks = map
+        .get_mut("data")
+        .and_then(Value::as_array_mut)
+        .context("expected a list of tasks")?;
+    for (i, task) in tasks.iter_mut().enumerate() {
+        task.as_object_mut()
+            .context("expected task to be an object")?
+            .insert("id".to_string(), Value::from(i + 1));
+    }
+    let last_id = tasks.len();
+    map.insert("last_id".to_string(), Value::from(last_id));
+    Ok(value)
+}
+
+/// Version 4 added the misfire policy (existing tasks keep being skipped if missed)
+fn add_misfire_policy(mut value: Value) -> anyhow::Result<Value> {
+    let tasks = value
+        .get_mut("data")
+        .and_then(Value::as_array_mut)
+        .context("expected a list of tasks")?;
+    for task in tasks {
+        task.as_object_mut()
+            .context("expected task to be an object")?
+            .insert(
+                "misfire_policy".to_string(),
+                serde_json::to_value(MisfirePolicy::SkipAndNotify)?,
+            );
+    }
+    Ok(value)
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub enum Objective {
     UnrankedStartEvent,
-}
 
-pub enum OutcomeCreateScheduledTask {
-    Created,
+    /// Posts the message in the channel
+    Announcement {
+        channel_id: ChannelId,
+        message: String,
+    },
 
-    /// Includes the previous timestamp
-    Replaced(UnixTimestamp),
+    /// Reminds everyone in the unranked channel to vote and shows the current ideas
+    VotingClosesReminder {
+        closes_at: UnixTimestamp,
+    },
+
+    /// Posts the current scores
+    LeaderboardPost {
+        channel_id: ChannelId,
+    },
+
+    /// Announces the end of the season and posts the final standings
+    SeasonEnd {
+        channel_id: ChannelId,
+    },
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct ScheduledTask {
+    pub id: ScheduledTaskId,
     pub desired_execution_timestamp: UnixTimestamp,
     pub objective: Objective,
+    /// If set the task is rescheduled after it fires instead of being removed
+    pub recurrence: Option<Recurrence>,
+    /// What to do if the bot was not running when the task was due
+    pub misfire_policy: MisfirePolicy,
     #[serde(skip)]
     task: Option<JoinHandle<()>>,
+    /// Reminders posted before the task runs. They are not stored as they are recreated from the
+    /// config each time the task is spawned
+    #[serde(skip)]
+    reminders: Vec<JoinHandle<()>>,
 }
 
 enum OutcomeSpawnTask {
@@ -88,20 +245,26 @@ enum OutcomeSpawnTask {
     SucceededFromEmpty,
 }
 
+/// What was done with a task that was due while the bot was not running
+enum OutcomeMisfire {
+    Ran,
+    Rescheduled(UnixTimestamp),
+    Skipped,
+}
+
 impl ScheduledTask {
     /// Returns true iff it was able to successfully spawn the task
-    #[instrument(skip(self, data))]
-    fn spawn_task(&mut self, data: Data) -> anyhow::Result<OutcomeSpawnTask> {
+    #[instrument(skip(self, runner))]
+    fn spawn_task(&mut self, runner: impl TaskRunner) -> anyhow::Result<OutcomeSpawnTask> {
         info!("START");
-        let had_handle = if let Some(old_handle) = self.task.take() {
-            info!("Aborting previous handle for {}", self.objective);
-            old_handle.abort();
-            true
+        let had_handle = self.abort();
+        if had_handle {
+            info!("Aborted previous handle for {}", self.objective);
         } else {
             info!("No previously spawned task to abort");
-            false
-        };
-        self.do_spawn(data)?;
+        }
+        let sleep_duration = self.duration_until_due(runner.clock().now()?)?;
+        self.do_spawn(runner, sleep_duration)?;
         let result = if had_handle {
             OutcomeSpawnTask::SucceededReplaced
         } else {
@@ -111,190 +274,837 @@ impl ScheduledTask {
         Ok(result)
     }
 
-    /// Spawns a new task and saves the join handle
-    /// Previous task should already be aborted and cleared
-    /// as any currently stored handle will be lost
-    #[instrument(skip(self, data) fields(self.objective = %self.objective, self.desired_execution_timestamp = ?self.desired_execution_timestamp))]
-    fn do_spawn(&mut self, data: Data) -> anyhow::Result<()> {
-        let objective = self.objective;
-        debug_assert!(
-            self.task.is_none(),
-            "task should have been aborted already if it existed"
-        );
-        let timestamp_now = UnixTimestamp::now()?;
+    /// Aborts the spawned task along with its reminders. Returns true iff a task was spawned
+    fn abort(&mut self) -> bool {
+        for reminder in self.reminders.drain(..) {
+            reminder.abort();
+        }
+        match self.task.take() {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fails if the task is already due
+    fn duration_until_due(&self, timestamp_now: UnixTimestamp) -> anyhow::Result<Duration> {
         info!("timestamp_now={timestamp_now:?}");
-        let seconds_to_desired = self.desired_execution_timestamp.0 - timestamp_now.0;
+        let seconds_to_desired = self
+            .desired_execution_timestamp
+            .seconds_since(timestamp_now);
         info!(seconds_to_desired);
         if seconds_to_desired <= 0 {
-            let duration_in_past = Duration::from_secs(seconds_to_desired.unsigned_abs() as _);
+            let duration_in_past = Duration::from_secs(seconds_to_desired.unsigned_abs());
             let err_msg = format!(
                 "unable to schedule task because duration is {} in the past",
                 duration_in_past.to_human_time_string()
             );
             error!(err_msg);
-            bail!(err_msg);
+            bail!(UserError::new(err_msg));
         }
-        let sleep_duration = Duration::from_secs(
-            seconds_to_desired
-                .try_into()
-                .context("failed to convert seconds to sleep into a duration")?,
-        );
+        Ok(Duration::from_secs(seconds_to_desired.unsigned_abs()))
+    }
 
+    /// Spawns a new task and saves the join handle
+    /// Previous task should already be aborted and cleared
+    /// as any currently stored handle will be lost
+    #[instrument(skip(self, runner) fields(self.id = %self.id, self.objective = %self.objective, self.desired_execution_timestamp = ?self.desired_execution_timestamp))]
+    fn do_spawn(
+        &mut self,
+        runner: impl TaskRunner,
+        sleep_duration: Duration,
+    ) -> anyhow::Result<()> {
+        let id = self.id;
+        let objective = self.objective.clone();
+        let scheduled_for = self.desired_execution_timestamp;
+        debug_assert!(
+            self.task.is_none() && self.reminders.is_empty(),
+            "task should have been aborted already if it existed"
+        );
+        self.spawn_reminders(&runner)?;
         self.task = Some(tokio::spawn(async move {
             // Sleep until it's time to work
             info!(
                 "spawned event started, going to sleep for {}",
                 sleep_duration.to_human_time_string()
             );
-            tokio::time::sleep(sleep_duration).await;
+            runner.clock().sleep(sleep_duration).await;
             info!("sleeping task has woken up with objective: {objective}");
 
             // Do the objective
-            let cmd_result = match objective {
-                Objective::UnrankedStartEvent => {
-                    do_start_event(
-                        data.inner.ctx.clone(),
-                        data.inner.shared_config.channel_unranked,
-                        &data,
-                    )
-                    .await
+            let ran_at = runner.clock().now().unwrap_or(scheduled_for);
+            let cmd_result = runner.do_objective(&objective).await;
+
+            // Check result of objective
+            let outcome = match cmd_result {
+                Ok(()) => {
+                    info!("objective accomplished");
+                    ExecutionOutcome::Succeeded
+                }
+                Err(e) => {
+                    error!("failed to accomplish objective with error: {e:?}");
+                    ExecutionOutcome::Failed {
+                        error: format!("{e:#}"),
+                    }
                 }
             };
+            let execution = Execution {
+                task_id: id,
+                objective,
+                scheduled_for,
+                ran_at,
+                outcome,
+            };
+            runner.report_execution(execution).await;
 
-            // Check result of objective
-            match cmd_result {
-                Ok(()) => info!("objective accomplished"),
-                Err(e) => error!("failed to accomplish objective with error: {e:?}"),
+            // Remove or reschedule the task (We can only do this as we are running from a different task as the mutex is locked rn and we would create a deadlock if this were on the same execution path)
+            match runner.task_fired(id) {
+                Ok(Some(next)) => info!("task rescheduled for {next:?}"),
+                Ok(None) => info!("task removed after firing"),
+                Err(e) => error!("failed to remove or reschedule the task with error: {e:?}"),
             }
+        }));
+        Ok(())
+    }
 
-            // Remove task from list (We can only do this as we are running from a different task as the mutex is locked rn and we would create a deadlock if this were on the same execution path)
-            if let Err(e) = data.schedule_cancel_task_by_objective(objective) {
-                error!("failed to remove the task from with error: {e:?}");
+    /// Spawns the reminders before an unranked start that are still in the future. They are tied
+    /// to this task so they move or get cancelled along with it
+    fn spawn_reminders(&mut self, runner: &impl TaskRunner) -> anyhow::Result<()> {
+        if !matches!(self.objective, Objective::UnrankedStartEvent) {
+            return Ok(());
+        }
+        let now = runner.clock().now()?;
+        let starts_at = self.desired_execution_timestamp;
+        for offset in runner.reminder_offsets().iter() {
+            let offset_secs: i64 = offset
+                .as_secs()
+                .try_into()
+                .context("reminder offset too large")?;
+            let seconds_to_reminder = starts_at.seconds_since(now) - offset_secs;
+            if seconds_to_reminder <= 0 {
+                info!(
+                    "Skipping reminder {} before start as that has already passed",
+                    offset.to_human_time_string()
+                );
+                continue;
             }
-        }));
+            let sleep_duration = Duration::from_secs(seconds_to_reminder.unsigned_abs());
+            let runner = runner.clone();
+            self.reminders.push(tokio::spawn(async move {
+                runner.clock().sleep(sleep_duration).await;
+                if let Err(e) = runner.do_start_event_reminder(starts_at).await {
+                    error!("failed to post reminder before unranked start with error: {e:?}");
+                }
+            }));
+        }
         Ok(())
     }
 
-    fn new(objective: Objective, desired_execution_timestamp: UnixTimestamp) -> Self {
+    /// Applies the misfire policy to a task that should have already run
+    fn handle_misfire(
+        &mut self,
+        seconds_late: u64,
+        now: UnixTimestamp,
+        runner: impl TaskRunner,
+    ) -> anyhow::Result<OutcomeMisfire> {
+        if self.misfire_policy.should_run(seconds_late) {
+            warn!(
+                "{} missed its run by {seconds_late}s, running now",
+                self.objective
+            );
+            self.do_spawn(runner, Duration::ZERO)?;
+            return Ok(OutcomeMisfire::Ran);
+        }
+        let Some(recurrence) = &self.recurrence else {
+            warn!(
+                "{} missed its run by {seconds_late}s, skipping",
+                self.objective
+            );
+            return Ok(OutcomeMisfire::Skipped);
+        };
+        let next = recurrence.next_after(self.desired_execution_timestamp, now)?;
+        warn!(
+            "{} missed its run by {seconds_late}s, moved to {next:?}",
+            self.objective
+        );
+        self.desired_execution_timestamp = next;
+        self.spawn_task(runner)?;
+        Ok(OutcomeMisfire::Rescheduled(next))
+    }
+
+    fn new(
+        id: ScheduledTaskId,
+        objective: Objective,
+        desired_execution_timestamp: UnixTimestamp,
+        recurrence: Option<Recurrence>,
+        misfire_policy: MisfirePolicy,
+    ) -> Self {
         Self {
+            id,
             desired_execution_timestamp,
             objective,
+            recurrence,
+            misfire_policy,
             task: None,
+            reminders: Vec::new(),
         }
     }
 }
 
 impl ScheduledTasks {
-    const DATA_KEY: &'static str = "scheduled_tasks";
+    pub const DATA_KEY: &'static str = "scheduled_tasks";
 
-    #[instrument(skip(self, data))]
+    /// Adds a new task (other tasks with the same objective are kept) and returns its ID
+    #[instrument(skip(self, runner))]
     pub fn create_task(
         &mut self,
         objective: Objective,
         desired_execution_timestamp: UnixTimestamp,
-        data: Data,
-    ) -> anyhow::Result<OutcomeCreateScheduledTask> {
-        if let Some(existing) = self.find_task(objective) {
-            let prev_timestamp = existing.desired_execution_timestamp;
-            existing.desired_execution_timestamp = desired_execution_timestamp;
-            existing.spawn_task(data)?;
-            Ok(OutcomeCreateScheduledTask::Replaced(prev_timestamp))
-        } else {
-            let mut task = ScheduledTask::new(objective, desired_execution_timestamp);
-            task.spawn_task(data)?;
-            self.data.push(task);
-            Ok(OutcomeCreateScheduledTask::Created)
-        }
+        recurrence: Option<Recurrence>,
+        misfire_policy: MisfirePolicy,
+        runner: impl TaskRunner,
+    ) -> anyhow::Result<ScheduledTaskId> {
+        let id = self.next_id();
+        let mut task = ScheduledTask::new(
+            id,
+            objective,
+            desired_execution_timestamp,
+            recurrence,
+            misfire_policy,
+        );
+        task.spawn_task(runner)?;
+        self.data.push(task);
+        self.last_id += 1;
+        info!("Created task with ID: {id}");
+        Ok(id)
     }
 
-    pub fn find_task(&mut self, objective: Objective) -> Option<&mut ScheduledTask> {
-        self.data
-            .iter_mut()
-            .find(|task| task.objective == objective)
+    fn next_id(&self) -> ScheduledTaskId {
+        ScheduledTaskId(
+            NonZeroUsize::new(self.last_id + 1).expect("any usize plus 1 must be non-zero"),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
     }
 
-    /// Creates the tasks from the saved data after restarting the application
-    #[instrument(skip(self, data))]
-    pub fn hydrate(&mut self, data: Data) {
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn position(&self, id: ScheduledTaskId) -> anyhow::Result<usize> {
+        match self.data.iter().position(|task| task.id == id) {
+            Some(index) => Ok(index),
+            None => bail!(UserError::new(format!(
+                "No scheduled task with ID: {id}. {}",
+                if self.data.is_empty() {
+                    "There are NO scheduled tasks.".to_string()
+                } else {
+                    format!(
+                        "Valid IDs are {}",
+                        self.data
+                            .iter()
+                            .map(|task| task.id.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+            ))),
+        }
+    }
+
+    /// Creates the tasks from the saved data after restarting the application.
+    ///
+    /// Tasks that are already due are handled according to their [`MisfirePolicy`]. Returns a
+    /// description of what happened to each task that was not simply scheduled as normal
+    #[instrument(skip(self, runner))]
+    pub fn hydrate(&mut self, runner: impl TaskRunner) -> anyhow::Result<Vec<String>> {
         info!("START");
+        let now = runner.clock().now()?;
+        let mut result = Vec::new();
         for i in (0..self.data.len()).rev() {
-            match self.data[i].spawn_task(data.clone()) {
-                Ok(_) => (),
+            let task = &mut self.data[i];
+            let due = task.desired_execution_timestamp;
+            let seconds_late = now.seconds_since(due);
+            let outcome = if seconds_late >= 0 {
+                task.handle_misfire(seconds_late.unsigned_abs(), now, runner.clone())
+                    .map(Some)
+            } else {
+                task.spawn_task(runner.clone()).map(|_| None)
+            };
+            let description = format!("{} (ID: {})", task.objective, task.id);
+            let late = || Duration::from_secs(seconds_late.unsigned_abs()).to_human_time_string();
+            match outcome {
+                Ok(None) => (),
+                Ok(Some(OutcomeMisfire::Ran)) => {
+                    result.push(format!(
+                        "{description} was due {due} ({} ago) and is being run now",
+                        late()
+                    ));
+                }
+                Ok(Some(OutcomeMisfire::Rescheduled(next))) => {
+                    result.push(format!("{description} was due {due} ({} ago) and was skipped. Next occurrence is {next}", late()));
+                }
+                Ok(Some(OutcomeMisfire::Skipped)) => {
+                    result.push(format!(
+                        "{description} was due {due} ({} ago) and was skipped and removed",
+                        late()
+                    ));
+                    self.data.remove(i);
+                }
                 Err(e) => {
                     error!(
-                        "Removing task with objective {} because failed to hydrate with error: {e:?}",
-                        self.data[i].objective
+                        "Removing task {description} because failed to hydrate with error: {e:?}"
                     );
+                    result.push(format!(
+                        "{description} was removed because it could not be scheduled: {e:#}"
+                    ));
                     self.data.remove(i);
                 }
             };
         }
         info!("END");
+        Ok(result)
+    }
+
+    /// Describes what [`Self::hydrate`] would do with each task that is already due at `now`
+    /// without changing anything (eg. to preview a restore)
+    pub fn overdue(&self, now: UnixTimestamp) -> Vec<String> {
+        self.data
+            .iter()
+            .filter_map(|task| {
+                let due = task.desired_execution_timestamp;
+                let seconds_late = now.seconds_since(due);
+                if seconds_late < 0 {
+                    return None;
+                }
+                let seconds_late = seconds_late.unsigned_abs();
+                let action = if task.misfire_policy.should_run(seconds_late) {
+                    "will run immediately"
+                } else if task.recurrence.is_some() {
+                    "will be skipped and moved to its next occurrence"
+                } else {
+                    "will be skipped and removed"
+                };
+                Some(format!(
+                    "{} (ID: {}) was due {due} ({} ago) and {action}",
+                    task.objective,
+                    task.id,
+                    Duration::from_secs(seconds_late).to_human_time_string()
+                ))
+            })
+            .collect()
+    }
+
+    /// Aborts all spawned tasks (the data is kept)
+    pub fn abort_all(&mut self) {
+        for task in self.data.iter_mut() {
+            if task.abort() {
+                info!("Aborted task for {}", task.objective);
+            }
+        }
+    }
+
+    pub fn find_task_by_id(&self, id: ScheduledTaskId) -> anyhow::Result<&ScheduledTask> {
+        Ok(&self.data[self.position(id)?])
     }
 
     #[instrument(skip(self))]
     pub fn cancel_task_by_id(&mut self, id: ScheduledTaskId) -> anyhow::Result<ScheduledTask> {
         info!("START");
-        let index = id.as_index();
-        if index < self.data.len() {
-            info!("ENDING with removal");
-            Ok(self.data.remove(index))
-        } else {
-            warn!("ENDING with out of bounds");
-            bail!("Invalid ID received for cancel of {id}");
-        }
+        let index = self.position(id)?;
+        let mut result = self.data.remove(index);
+        result.abort();
+        info!("ENDING with removal");
+        Ok(result)
     }
 
-    #[instrument(skip(self))]
-    pub fn cancel_task_by_objective(
+    /// Applies the changes to the task and respawns it. Returns the task as it was before and
+    /// after the changes
+    #[instrument(skip(self, runner))]
+    pub fn edit_task(
         &mut self,
-        objective: Objective,
-    ) -> anyhow::Result<ScheduledTask> {
+        id: ScheduledTaskId,
+        edit: TaskEdit,
+        runner: impl TaskRunner,
+    ) -> anyhow::Result<(String, String)> {
         info!("START");
-        let index = self.data.iter().enumerate().find_map(|(i, task)| {
-            if task.objective == objective {
-                Some(i)
-            } else {
-                None
-            }
-        });
-        if let Some(index) = index {
-            info!("ENDING with removal");
-            Ok(self.data.remove(index))
-        } else {
-            warn!("ENDING with objective not found");
-            bail!("Unable to find any scheduled task with objective: {objective}");
+        let index = self.position(id)?;
+        let mut edited = edit.apply(&self.data[index])?;
+        // Spawn the edited task first so the original is left running if that fails
+        edited.spawn_task(runner)?;
+        let mut original = std::mem::replace(&mut self.data[index], edited);
+        original.abort();
+        info!("END");
+        Ok((original.to_string(), self.data[index].to_string()))
+    }
+
+    /// Called by a task once it has run. Removes it or if it recurs moves it to the next
+    /// occurrence and returns that
+    #[instrument(skip(self, runner))]
+    pub fn task_fired(
+        &mut self,
+        id: ScheduledTaskId,
+        runner: impl TaskRunner,
+    ) -> anyhow::Result<Option<UnixTimestamp>> {
+        info!("START");
+        let index = self.position(id)?;
+        let task = &mut self.data[index];
+        let Some(recurrence) = task.recurrence.as_ref() else {
+            // The handle is for the task calling this function so it must not be aborted
+            self.data.remove(index);
+            info!("END");
+            return Ok(None);
+        };
+        let now = runner.clock().now()?;
+        let next = recurrence.next_after(task.desired_execution_timestamp, now)?;
+        task.desired_execution_timestamp = next;
+        // The handle is for the task calling this function so it must not be aborted (the
+        // reminders have all run before it)
+        task.task = None;
+        task.reminders.clear();
+        let sleep_duration = task.duration_until_due(now)?;
+        task.do_spawn(runner, sleep_duration)?;
+        info!("END");
+        Ok(Some(next))
+    }
+}
+
+impl Objective {
+    /// How much of an announcement is included when displaying the objective
+    const ANNOUNCEMENT_PREVIEW_CHARS: usize = 50;
+
+    /// The name of the variant (eg. for grouping in metrics)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Objective::UnrankedStartEvent => "UnrankedStartEvent",
+            Objective::Announcement { .. } => "Announcement",
+            Objective::VotingClosesReminder { .. } => "VotingClosesReminder",
+            Objective::LeaderboardPost { .. } => "LeaderboardPost",
+            Objective::SeasonEnd { .. } => "SeasonEnd",
         }
     }
 }
 
 impl Display for Objective {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Objective::UnrankedStartEvent => "UnrankedStartEvent",
+        match self {
+            Objective::UnrankedStartEvent => write!(f, "UnrankedStartEvent"),
+            Objective::Announcement {
+                channel_id,
+                message,
+            } => {
+                let mut preview: String = message
+                    .chars()
+                    .take(Self::ANNOUNCEMENT_PREVIEW_CHARS)
+                    .collect();
+                if preview.len() < message.len() {
+                    preview.push('…');
+                }
+                write!(f, "Announcement in {} ({preview:?})", channel_id.mention())
             }
-        )
+            Objective::VotingClosesReminder { closes_at } => {
+                write!(f, "VotingClosesReminder (closes {closes_at})")
+            }
+            Objective::LeaderboardPost { channel_id } => {
+                write!(f, "LeaderboardPost in {}", channel_id.mention())
+            }
+            Objective::SeasonEnd { channel_id } => {
+                write!(f, "SeasonEnd in {}", channel_id.mention())
+            }
+        }
     }
 }
 
+impl ScheduledTask {
+    /// How many occurrences after the scheduled one are shown for recurring tasks
+    const UPCOMING_TO_DISPLAY: usize = 3;
+}
+
 impl Display for ScheduledTask {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Objective: {}, Scheduled for {}",
-            self.objective, self.desired_execution_timestamp
-        )
+            "ID: {} - Objective: {}, Scheduled for {}",
+            self.id, self.objective, self.desired_execution_timestamp
+        )?;
+        write!(f, "\n  If missed: {}", self.misfire_policy)?;
+        if let Some(recurrence) = &self.recurrence {
+            write!(f, "\n  Repeats {recurrence}")?;
+            match recurrence.upcoming(self.desired_execution_timestamp, Self::UPCOMING_TO_DISPLAY) {
+                Ok(upcoming) => {
+                    for timestamp in upcoming {
+                        write!(f, "\n  - then {timestamp}")?;
+                    }
+                }
+                Err(e) => write!(f, "\n  Unable to calculate upcoming occurrences: {e}")?,
+            }
+        }
+        Ok(())
     }
 }
 
 impl Display for ScheduledTasks {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, task) in self.data.iter().enumerate() {
-            writeln!(f, "{}. {}", i + 1, task)?;
+        for task in self.data.iter() {
+            writeln!(f, "{task}")?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroU16,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::clock::{Clock, TestClock};
+
+    use super::*;
+
+    const START: i64 = 1_893_456_000;
+    const MINUTE: u64 = 60;
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    /// Runs the tasks without Discord by recording what would have been done
+    #[derive(Clone)]
+    struct TestRunner {
+        inner: Arc<TestRunnerInner>,
+    }
+
+    struct TestRunnerInner {
+        clock: TestClock,
+        reminder_offsets: Vec<Duration>,
+        tasks: Mutex<ScheduledTasks>,
+        executions: Mutex<Vec<Execution>>,
+        /// When each reminder was posted
+        reminders: Mutex<Vec<UnixTimestamp>>,
+    }
+
+    impl TaskRunner for TestRunner {
+        fn clock(&self) -> &dyn Clock {
+            &self.inner.clock
+        }
+
+        fn reminder_offsets(&self) -> &[Duration] {
+            &self.inner.reminder_offsets
+        }
+
+        async fn do_objective(&self, objective: &Objective) -> anyhow::Result<()> {
+            match objective {
+                Objective::Announcement { message, .. } if message == "fail" => {
+                    bail!("failed on purpose")
+                }
+                _ => Ok(()),
+            }
+        }
+
+        async fn do_start_event_reminder(&self, _starts_at: UnixTimestamp) -> anyhow::Result<()> {
+            let now = self.clock().now()?;
+            self.inner.reminders.lock().unwrap().push(now);
+            Ok(())
+        }
+
+        async fn report_execution(&self, execution: Execution) {
+            self.inner.executions.lock().unwrap().push(execution);
+        }
+
+        fn task_fired(&self, id: ScheduledTaskId) -> anyhow::Result<Option<UnixTimestamp>> {
+            self.inner
+                .tasks
+                .lock()
+                .unwrap()
+                .task_fired(id, self.clone())
+        }
+    }
+
+    impl TestRunner {
+        /// Must be called from a test with tokio's time paused, the time starts at [`START`]
+        fn new(reminder_offsets: Vec<Duration>) -> Self {
+            Self {
+                inner: Arc::new(TestRunnerInner {
+                    clock: TestClock::new(UnixTimestamp::new(START)),
+                    reminder_offsets,
+                    tasks: Default::default(),
+                    executions: Default::default(),
+                    reminders: Default::default(),
+                }),
+            }
+        }
+
+        fn tasks(&self) -> std::sync::MutexGuard<'_, ScheduledTasks> {
+            self.inner.tasks.lock().unwrap()
+        }
+
+        /// Creates a task that runs `seconds` after [`START`]
+        fn create(
+            &self,
+            objective: Objective,
+            seconds: i64,
+            recurrence: Option<&str>,
+        ) -> anyhow::Result<ScheduledTaskId> {
+            self.tasks().create_task(
+                objective,
+                UnixTimestamp::new(START + seconds),
+                recurrence.map(|x| x.parse().unwrap()),
+                MisfirePolicy::default(),
+                self.clone(),
+            )
+        }
+
+        /// The ID and how long after [`START`] each task ran
+        fn ran(&self) -> Vec<(usize, i64)> {
+            self.inner
+                .executions
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|execution| (execution.task_id.0.get(), execution.ran_at.0 - START))
+                .collect()
+        }
+
+        /// The remaining tasks' IDs and how long after [`START`] they are due
+        fn remaining(&self) -> Vec<(usize, i64)> {
+            self.tasks()
+                .data
+                .iter()
+                .map(|task| (task.id.0.get(), task.desired_execution_timestamp.0 - START))
+                .collect()
+        }
+    }
+
+    fn leaderboard() -> Objective {
+        Objective::LeaderboardPost {
+            channel_id: ChannelId::new(1),
+        }
+    }
+
+    async fn sleep_minutes(minutes: u64) {
+        tokio::time::sleep(Duration::from_secs(minutes * MINUTE)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_when_due_then_removed() {
+        let runner = TestRunner::new(vec![]);
+        runner.create(leaderboard(), HOUR, None).unwrap();
+        sleep_minutes(59).await;
+        assert_eq!(runner.ran(), vec![]);
+        assert_eq!(runner.remaining(), vec![(1, HOUR)]);
+        sleep_minutes(2).await;
+        assert_eq!(runner.ran(), vec![(1, HOUR)]);
+        assert_eq!(runner.remaining(), vec![]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failure_is_recorded() {
+        let runner = TestRunner::new(vec![]);
+        let objective = Objective::Announcement {
+            channel_id: ChannelId::new(1),
+            message: "fail".to_string(),
+        };
+        runner.create(objective, HOUR, None).unwrap();
+        sleep_minutes(61).await;
+        let executions = runner.inner.executions.lock().unwrap();
+        assert_eq!(
+            executions[0].outcome,
+            ExecutionOutcome::Failed {
+                error: "failed on purpose".to_string()
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn create_in_the_past_fails() {
+        let runner = TestRunner::new(vec![]);
+        assert!(runner.create(leaderboard(), -1, None).is_err());
+        assert_eq!(runner.remaining(), vec![]);
+        // IDs are only used up by tasks that were created
+        assert_eq!(runner.create(leaderboard(), HOUR, None).unwrap().0.get(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recurring_task_moves_to_next_occurrence() {
+        let runner = TestRunner::new(vec![]);
+        runner.create(leaderboard(), HOUR, Some("1")).unwrap();
+        sleep_minutes(61).await;
+        assert_eq!(runner.ran(), vec![(1, HOUR)]);
+        assert_eq!(runner.remaining(), vec![(1, HOUR + DAY)]);
+        sleep_minutes(24 * 60).await;
+        assert_eq!(runner.ran(), vec![(1, HOUR), (1, HOUR + DAY)]);
+        assert_eq!(runner.remaining(), vec![(1, HOUR + 2 * DAY)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_task_does_not_run() {
+        let runner = TestRunner::new(vec![Duration::from_secs(30 * MINUTE)]);
+        let id = runner
+            .create(Objective::UnrankedStartEvent, HOUR, None)
+            .unwrap();
+        runner.create(leaderboard(), 2 * HOUR, None).unwrap();
+        runner.tasks().cancel_task_by_id(id).unwrap();
+        sleep_minutes(3 * 60).await;
+        assert_eq!(runner.ran(), vec![(2, 2 * HOUR)]);
+        assert_eq!(*runner.inner.reminders.lock().unwrap(), vec![]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn edit_respawns_task() {
+        let runner = TestRunner::new(vec![]);
+        let id = runner.create(leaderboard(), HOUR, None).unwrap();
+        let edit = TaskEdit {
+            desired_execution_timestamp: Some(UnixTimestamp::new(START + 3 * HOUR)),
+            ..Default::default()
+        };
+        runner.tasks().edit_task(id, edit, runner.clone()).unwrap();
+        // A time in the past is rejected and the task is left as it was
+        let edit = TaskEdit {
+            desired_execution_timestamp: Some(UnixTimestamp::new(START - HOUR)),
+            ..Default::default()
+        };
+        assert!(runner.tasks().edit_task(id, edit, runner.clone()).is_err());
+        sleep_minutes(2 * 60).await;
+        assert_eq!(runner.ran(), vec![]);
+        sleep_minutes(2 * 60).await;
+        assert_eq!(runner.ran(), vec![(1, 3 * HOUR)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn replacing_aborts_old_tasks() {
+        let runner = TestRunner::new(vec![]);
+        runner.create(leaderboard(), HOUR, None).unwrap();
+        let mut replacement = ScheduledTasks::default();
+        replacement.data.push(ScheduledTask::new(
+            ScheduledTaskId(NonZeroUsize::new(7).unwrap()),
+            leaderboard(),
+            UnixTimestamp::new(START + 2 * HOUR),
+            None,
+            MisfirePolicy::default(),
+        ));
+        replacement.last_id = 7;
+        {
+            let mut tasks = runner.tasks();
+            tasks.abort_all();
+            *tasks = replacement;
+            assert_eq!(tasks.hydrate(runner.clone()).unwrap(), Vec::<String>::new());
+        }
+        sleep_minutes(3 * 60).await;
+        assert_eq!(runner.ran(), vec![(7, 2 * HOUR)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hydrate_applies_misfire_policy() {
+        let runner = TestRunner::new(vec![]);
+        let task = |id: usize, seconds: i64, recurrence: Option<&str>, misfire_policy| {
+            ScheduledTask::new(
+                ScheduledTaskId(NonZeroUsize::new(id).unwrap()),
+                leaderboard(),
+                UnixTimestamp::new(START + seconds),
+                recurrence.map(|x| x.parse().unwrap()),
+                misfire_policy,
+            )
+        };
+        let within_2_hours = MisfirePolicy::RunIfLateWithinHours(NonZeroU16::new(2).unwrap());
+        let mut saved = ScheduledTasks {
+            data: vec![
+                task(1, HOUR, None, MisfirePolicy::SkipAndNotify),
+                task(2, -HOUR, None, MisfirePolicy::RunImmediately),
+                task(3, -HOUR, None, MisfirePolicy::SkipAndNotify),
+                task(4, -3 * HOUR, None, within_2_hours),
+                task(5, -HOUR, None, within_2_hours),
+                task(6, -HOUR, Some("1"), MisfirePolicy::SkipAndNotify),
+            ],
+            last_id: 6,
+        };
+        let preview = saved.overdue(UnixTimestamp::new(START));
+        let will_run: Vec<_> = preview
+            .iter()
+            .filter(|x| x.ends_with("will run immediately"))
+            .collect();
+        assert_eq!(preview.len(), 5, "{preview:#?}");
+        assert_eq!(will_run.len(), 2, "{preview:#?}");
+        let messages = saved.hydrate(runner.clone()).unwrap();
+        assert_eq!(messages.len(), 5, "{messages:#?}");
+        *runner.tasks() = saved;
+        assert_eq!(
+            runner.remaining(),
+            vec![(1, HOUR), (2, -HOUR), (5, -HOUR), (6, DAY - HOUR)]
+        );
+        sleep_minutes(1).await;
+        let mut ran = runner.ran();
+        ran.sort();
+        assert_eq!(ran, vec![(2, 0), (5, 0)]);
+        sleep_minutes(60).await;
+        assert_eq!(runner.ran()[2..], [(1, HOUR)]);
+        assert_eq!(runner.remaining(), vec![(6, DAY - HOUR)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reminders_before_unranked_start() {
+        let offsets = [2 * HOUR as u64, 30 * MINUTE, 10 * MINUTE]
+            .map(Duration::from_secs)
+            .to_vec();
+        let runner = TestRunner::new(offsets);
+        runner
+            .create(Objective::UnrankedStartEvent, HOUR, None)
+            .unwrap();
+        sleep_minutes(61).await;
+        let reminders: Vec<_> = runner
+            .inner
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|x| x.0 - START)
+            .collect();
+        // The reminder 2 hours before had already passed when the task was created
+        assert_eq!(reminders, vec![30 * 60, 50 * 60]);
+        assert_eq!(runner.ran(), vec![(1, HOUR)]);
+    }
+
+    #[test]
+    fn objectives_keep_their_stored_format() {
+        let objectives = vec![
+            Objective::UnrankedStartEvent,
+            Objective::Announcement {
+                channel_id: ChannelId::new(1),
+                message: "Hi".to_string(),
+            },
+            Objective::VotingClosesReminder {
+                closes_at: UnixTimestamp::new(2),
+            },
+            Objective::LeaderboardPost {
+                channel_id: ChannelId::new(3),
+            },
+            Objective::SeasonEnd {
+                channel_id: ChannelId::new(4),
+            },
+        ];
+        let expected = r#"["UnrankedStartEvent",{"Announcement":{"channel_id":"1","message":"Hi"}},{"VotingClosesReminder":{"closes_at":2}},{"LeaderboardPost":{"channel_id":"3"}},{"SeasonEnd":{"channel_id":"4"}}]"#;
+        assert_eq!(serde_json::to_string(&objectives).unwrap(), expected);
+        let loaded: Vec<Objective> = serde_json::from_str(expected).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), expected);
+    }
+
+    #[test]
+    fn timestamp_past_2038() {
+        // Largest value that fitted when stored as an i32 still loads
+        let old: UnixTimestamp = serde_json::from_str("2147483647").unwrap();
+        assert_eq!(old.0, i64::from(i32::MAX));
+
+        let later = UnixTimestamp::new(old.0 + 1);
+        assert_eq!(serde_json::to_string(&later).unwrap(), "2147483648");
+        assert_eq!(later.duration_since(old), Some(Duration::from_secs(1)));
+        assert_eq!(old.duration_since(later), None);
+        assert_eq!(later.discord_tag('F'), "<t:2147483648:F>");
+        assert_eq!(
+            later.format_in(chrono_tz::America::New_York),
+            "Mon, 18 Jan 2038 22:14 EST"
+        );
+    }
+}
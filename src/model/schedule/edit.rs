@@ -0,0 +1,163 @@
+//! Changes to an existing scheduled task
+
+use anyhow::bail;
+use poise::serenity_prelude::ChannelId;
+
+use crate::user_error::UserError;
+
+use super::{MisfirePolicy, Objective, Recurrence, ScheduledTask, UnixTimestamp};
+
+/// The changes to make to a task. Fields that are `None` are left as they are
+#[derive(Debug, Default)]
+pub struct TaskEdit {
+    pub desired_execution_timestamp: Option<UnixTimestamp>,
+    /// Only valid for objectives that post in a channel
+    pub channel_id: Option<ChannelId>,
+    /// Only valid for announcements
+    pub message: Option<String>,
+    /// Only valid for voting reminders
+    pub closes_at: Option<UnixTimestamp>,
+    /// `Some(None)` stops the task from repeating
+    pub recurrence: Option<Option<Recurrence>>,
+    pub misfire_policy: Option<MisfirePolicy>,
+}
+
+impl TaskEdit {
+    pub fn is_empty(&self) -> bool {
+        self.desired_execution_timestamp.is_none()
+            && self.channel_id.is_none()
+            && self.message.is_none()
+            && self.closes_at.is_none()
+            && self.recurrence.is_none()
+            && self.misfire_policy.is_none()
+    }
+
+    /// Returns a copy of `task` with the changes applied (the copy is not spawned). Fails if a
+    /// change does not apply to the task's objective
+    pub(super) fn apply(self, task: &ScheduledTask) -> anyhow::Result<ScheduledTask> {
+        if self.is_empty() {
+            bail!(UserError::new(
+                "Nothing to change. Provide at least one value to edit"
+            ));
+        }
+        let has_channel = matches!(
+            task.objective,
+            Objective::Announcement { .. }
+                | Objective::LeaderboardPost { .. }
+                | Objective::SeasonEnd { .. }
+        );
+        if self.channel_id.is_some() && !has_channel {
+            bail!(UserError::new(format!(
+                "{} does not post in a configurable channel",
+                task.objective
+            )));
+        }
+        if self.message.is_some() && !matches!(task.objective, Objective::Announcement { .. }) {
+            bail!(UserError::new("Only announcements have a message to edit"));
+        }
+        if self.closes_at.is_some()
+            && !matches!(task.objective, Objective::VotingClosesReminder { .. })
+        {
+            bail!(UserError::new(
+                "Only voting reminders have a closing time to edit"
+            ));
+        }
+        let mut objective = task.objective.clone();
+        match &mut objective {
+            Objective::Announcement {
+                channel_id,
+                message,
+            } => {
+                if let Some(new_channel_id) = self.channel_id {
+                    *channel_id = new_channel_id;
+                }
+                if let Some(new_message) = self.message {
+                    *message = new_message;
+                }
+            }
+            Objective::LeaderboardPost { channel_id } | Objective::SeasonEnd { channel_id } => {
+                if let Some(new_channel_id) = self.channel_id {
+                    *channel_id = new_channel_id;
+                }
+            }
+            Objective::VotingClosesReminder { closes_at } => {
+                if let Some(new_closes_at) = self.closes_at {
+                    *closes_at = new_closes_at;
+                }
+            }
+            Objective::UnrankedStartEvent => {}
+        }
+        Ok(ScheduledTask::new(
+            task.id,
+            objective,
+            self.desired_execution_timestamp
+                .unwrap_or(task.desired_execution_timestamp),
+            self.recurrence.unwrap_or_else(|| task.recurrence.clone()),
+            self.misfire_policy.unwrap_or(task.misfire_policy),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::model::schedule::ScheduledTaskId;
+
+    fn task(objective: Objective) -> ScheduledTask {
+        ScheduledTask::new(
+            ScheduledTaskId::from(NonZeroUsize::MIN),
+            objective,
+            UnixTimestamp::new(100),
+            Some("1".parse().unwrap()),
+            MisfirePolicy::SkipAndNotify,
+        )
+    }
+
+    #[test]
+    fn only_given_values_change() {
+        let original = task(Objective::Announcement {
+            channel_id: ChannelId::new(1),
+            message: "Hi".to_string(),
+        });
+        let edit = TaskEdit {
+            channel_id: Some(ChannelId::new(2)),
+            recurrence: Some(None),
+            ..Default::default()
+        };
+        let edited = edit.apply(&original).unwrap();
+        assert_eq!(edited.id, original.id);
+        assert_eq!(edited.desired_execution_timestamp, UnixTimestamp::new(100));
+        assert_eq!(edited.recurrence, None);
+        assert_eq!(edited.misfire_policy, MisfirePolicy::SkipAndNotify);
+        let Objective::Announcement {
+            channel_id,
+            message,
+        } = edited.objective
+        else {
+            panic!("objective type should not change");
+        };
+        assert_eq!(channel_id, ChannelId::new(2));
+        assert_eq!(message, "Hi");
+    }
+
+    #[test]
+    fn invalid() {
+        let start = task(Objective::UnrankedStartEvent);
+        assert!(TaskEdit::default().apply(&start).is_err());
+        let edit = TaskEdit {
+            channel_id: Some(ChannelId::new(2)),
+            ..Default::default()
+        };
+        assert!(edit.apply(&start).is_err());
+        let edit = TaskEdit {
+            message: Some("Hi".to_string()),
+            ..Default::default()
+        };
+        let leaderboard = task(Objective::LeaderboardPost {
+            channel_id: ChannelId::new(1),
+        });
+        assert!(edit.apply(&leaderboard).is_err());
+    }
+}
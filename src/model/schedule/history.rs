@@ -0,0 +1,154 @@
+//! Keeps a record of each time a scheduled task ran and how it went
+
+use std::{collections::VecDeque, fmt::Display, num::NonZeroUsize};
+
+use anyhow::bail;
+
+use crate::{
+    model::versioned::{self, Upgrade, Versioned},
+    user_error::UserError,
+};
+
+use super::{Objective, ScheduledTaskId, UnixTimestamp};
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct ExecutionHistory {
+    /// Oldest first
+    entries: VecDeque<Execution>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Execution {
+    pub task_id: ScheduledTaskId,
+    pub objective: Objective,
+    pub scheduled_for: UnixTimestamp,
+    pub ran_at: UnixTimestamp,
+    pub outcome: ExecutionOutcome,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    Succeeded,
+    Failed { error: String },
+}
+
+impl ExecutionHistory {
+    pub const DATA_KEY: &'static str = "schedule_history";
+    pub const DISPLAY_TITLE: &'static str = "Scheduled Task History";
+
+    /// Once there are more entries than this the oldest are removed
+    const MAX_ENTRIES: usize = 500;
+    const PAGE_SIZE: usize = 10;
+
+    pub async fn new(shared_config: &crate::SharedConfig) -> anyhow::Result<Self> {
+        shared_config.load_or_default_kv(Self::DATA_KEY).await
+    }
+
+    pub fn push(&mut self, execution: Execution) {
+        self.entries.push_back(execution);
+        while self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Always at least 1 so there is a page to show even when empty
+    pub fn page_count(&self) -> usize {
+        self.entries.len().div_ceil(Self::PAGE_SIZE).max(1)
+    }
+
+    /// Returns the entries on the page (1 is the most recent) one per line
+    pub fn page_as_string(&self, page: NonZeroUsize) -> anyhow::Result<String> {
+        let page_count = self.page_count();
+        if page.get() > page_count {
+            bail!(UserError::new(format!(
+                "Page {page} does not exist. There are {page_count} page(s)"
+            )));
+        }
+        if self.entries.is_empty() {
+            return Ok("No scheduled tasks have run yet".to_string());
+        }
+        Ok(self
+            .entries
+            .iter()
+            .rev()
+            .skip((page.get() - 1) * Self::PAGE_SIZE)
+            .take(Self::PAGE_SIZE)
+            .map(|execution| execution.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+impl Versioned for ExecutionHistory {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
+impl Execution {
+    /// How much of the error is shown so that a page of history fits in an embed
+    const ERROR_PREVIEW_CHARS: usize = 200;
+}
+
+impl Display for Execution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match &self.outcome {
+            ExecutionOutcome::Succeeded => "✅",
+            ExecutionOutcome::Failed { .. } => "❌",
+        };
+        write!(
+            f,
+            "{status} ID: {} - {} ran {} (scheduled for {})",
+            self.task_id,
+            self.objective,
+            self.ran_at.discord_tag('f'),
+            self.scheduled_for.discord_tag('f')
+        )?;
+        if let ExecutionOutcome::Failed { error } = &self.outcome {
+            let mut preview: String = error.chars().take(Self::ERROR_PREVIEW_CHARS).collect();
+            if preview.len() < error.len() {
+                preview.push('…');
+            }
+            write!(f, "\n  Error: {preview}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execution(i: usize) -> Execution {
+        Execution {
+            task_id: NonZeroUsize::new(i).unwrap().into(),
+            objective: Objective::UnrankedStartEvent,
+            scheduled_for: UnixTimestamp::new(0),
+            ran_at: UnixTimestamp::new(0),
+            outcome: ExecutionOutcome::Succeeded,
+        }
+    }
+
+    #[test]
+    fn pages_newest_first_and_old_entries_dropped() {
+        let mut history = ExecutionHistory::default();
+        assert_eq!(history.page_count(), 1);
+        for i in 1..=ExecutionHistory::MAX_ENTRIES + 5 {
+            history.push(execution(i));
+        }
+        assert_eq!(history.entries.len(), ExecutionHistory::MAX_ENTRIES);
+        assert_eq!(
+            history.page_count(),
+            ExecutionHistory::MAX_ENTRIES / ExecutionHistory::PAGE_SIZE
+        );
+        let first_page = history.page_as_string(NonZeroUsize::MIN).unwrap();
+        assert!(first_page.starts_with("✅ ID: 505 "));
+        let last_page = history
+            .page_as_string(NonZeroUsize::new(history.page_count()).unwrap())
+            .unwrap();
+        assert!(last_page.ends_with(&execution(6).to_string()));
+        assert!(
+            history
+                .page_as_string(NonZeroUsize::new(history.page_count() + 1).unwrap())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! What to do with a task whose time passed while the bot was not running
+
+use std::{fmt::Display, num::NonZeroU16, str::FromStr};
+
+use anyhow::Context as _;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MisfirePolicy {
+    /// Run as soon as the bot starts no matter how late
+    RunImmediately,
+
+    /// Run as soon as the bot starts if it is no more than this many hours late, otherwise skip
+    RunIfLateWithinHours(NonZeroU16),
+
+    /// Don't run (recurring tasks move to their next occurrence)
+    #[default]
+    SkipAndNotify,
+}
+
+impl MisfirePolicy {
+    /// Returns true if a task this many seconds late should still be run
+    pub fn should_run(&self, seconds_late: u64) -> bool {
+        match self {
+            MisfirePolicy::RunImmediately => true,
+            MisfirePolicy::RunIfLateWithinHours(hours) => {
+                seconds_late <= u64::from(hours.get()) * 60 * 60
+            }
+            MisfirePolicy::SkipAndNotify => false,
+        }
+    }
+}
+
+/// Accepts `run`, `skip` or a number of hours (run only if late by at most that many hours)
+impl FromStr for MisfirePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "run" => Ok(Self::RunImmediately),
+            "skip" => Ok(Self::SkipAndNotify),
+            _ => s
+                .parse()
+                .map(Self::RunIfLateWithinHours)
+                .with_context(|| {
+                    format!(
+                        "{s:?} is not a valid misfire policy. Expected \"run\", \"skip\" or a number of hours (1 or more)"
+                    )
+                }),
+        }
+    }
+}
+
+impl Display for MisfirePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MisfirePolicy::RunImmediately => write!(f, "run when the bot is back"),
+            MisfirePolicy::RunIfLateWithinHours(hours) => {
+                write!(
+                    f,
+                    "run if the bot is back within {hours} hour(s), otherwise skip"
+                )
+            }
+            MisfirePolicy::SkipAndNotify => write!(f, "skip"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::run("run", 1_000_000, true)]
+    #[case::skip("Skip", 1, false)]
+    #[case::within_limit("2", 2 * 60 * 60, true)]
+    #[case::past_limit("2", 2 * 60 * 60 + 1, false)]
+    fn should_run(#[case] policy: &str, #[case] seconds_late: u64, #[case] expected: bool) {
+        let policy: MisfirePolicy = policy.parse().unwrap();
+        assert_eq!(policy.should_run(seconds_late), expected);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("0".parse::<MisfirePolicy>().is_err());
+        assert!("later".parse::<MisfirePolicy>().is_err());
+    }
+}
@@ -1,13 +1,13 @@
-use std::sync::MutexGuard;
+use std::{num::NonZeroUsize, sync::MutexGuard};
 
 use anyhow::Context;
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
-use crate::Data;
+use crate::{Data, db::PendingSave};
 
 use super::{
-    Objective, OutcomeCreateScheduledTask, ScheduledTask, ScheduledTaskId, ScheduledTasks,
-    UnixTimestamp,
+    Execution, ExecutionHistory, MisfirePolicy, Objective, Recurrence, ScheduledTask,
+    ScheduledTaskId, ScheduledTasks, TaskEdit, UnixTimestamp,
 };
 
 impl Data {
@@ -19,19 +19,35 @@ impl Data {
         }
     }
 
-    fn save_scheduled_tasks(&self, data: &ScheduledTasks) -> anyhow::Result<()> {
+    /// Serves as the link to the private function that returns the guard
+    fn guard_schedule_history(&'_ self) -> anyhow::Result<MutexGuard<'_, ExecutionHistory>> {
+        match self.inner.schedule_history.lock() {
+            Ok(guard) => Ok(guard),
+            Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+        }
+    }
+
+    fn save_scheduled_tasks(&self, data: &ScheduledTasks) -> anyhow::Result<PendingSave> {
         self.save(ScheduledTasks::DATA_KEY, data)
     }
 
     #[instrument(skip(self))]
-    /// Add a new task to the scheduled tasks and replaces if a task with the same objective exists
+    /// Add a new task to the scheduled tasks and returns its ID
     pub fn schedule_create_task(
         &self,
         objective: Objective,
         desired_execution_timestamp: UnixTimestamp,
-    ) -> anyhow::Result<OutcomeCreateScheduledTask> {
+        recurrence: Option<Recurrence>,
+        misfire_policy: MisfirePolicy,
+    ) -> anyhow::Result<ScheduledTaskId> {
         let mut guard = self.guard_schedule()?;
-        let result = guard.create_task(objective, desired_execution_timestamp, self.clone())?;
+        let result = guard.create_task(
+            objective,
+            desired_execution_timestamp,
+            recurrence,
+            misfire_policy,
+            self.clone(),
+        )?;
         self.save_scheduled_tasks(&guard)?;
         Ok(result)
     }
@@ -45,22 +61,40 @@ impl Data {
         info!("END");
         Ok(result)
     }
+
     #[instrument(skip(self))]
-    pub fn schedule_cancel_task_by_objective(
+    /// Changes the task and returns how it was before and after
+    pub fn schedule_edit_task(
         &self,
-        objective: Objective,
-    ) -> anyhow::Result<ScheduledTask> {
+        id: ScheduledTaskId,
+        edit: TaskEdit,
+    ) -> anyhow::Result<(String, String)> {
+        info!("START");
+        let mut guard = self.guard_schedule()?;
+        let result = guard.edit_task(id, edit, self.clone())?;
+        self.save_scheduled_tasks(&guard)?;
+        info!("END");
+        Ok(result)
+    }
+
+    #[instrument(skip(self))]
+    /// Removes the task that just ran or reschedules it if it recurs (returns the next occurrence)
+    pub fn schedule_task_fired(
+        &self,
+        id: ScheduledTaskId,
+    ) -> anyhow::Result<Option<UnixTimestamp>> {
         info!("START");
         let mut guard = self.guard_schedule()?;
-        let result = guard.cancel_task_by_objective(objective)?;
+        let result = guard.task_fired(id, self.clone())?;
         self.save_scheduled_tasks(&guard)?;
         info!("END");
         Ok(result)
     }
 
     #[instrument(skip(self))]
-    /// Creates the tasks from the saved data after restarting the application
-    pub fn schedule_hydrate(&self) {
+    /// Creates the tasks from the saved data after restarting the application.
+    /// Returns what was done with tasks that were missed (see [`ScheduledTasks::hydrate`])
+    pub fn schedule_hydrate(&self) -> Vec<String> {
         let mut guard = match self
             .guard_schedule()
             .context("failed to get guard for schedule")
@@ -68,10 +102,93 @@ impl Data {
             Ok(guard) => guard,
             Err(e) => {
                 error!("unable to hydrate because of error: {e:?}");
-                return;
+                return vec![format!("Unable to start scheduled tasks: {e:#}")];
             }
         };
-        guard.hydrate(self.clone());
+        let result = match guard.hydrate(self.clone()) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("unable to hydrate because of error: {e:?}");
+                vec![format!("Unable to start scheduled tasks: {e:#}")]
+            }
+        };
+        if !result.is_empty()
+            && let Err(e) = self.save_scheduled_tasks(&guard)
+        {
+            error!("failed to save scheduled tasks after hydrating: {e:?}");
+        }
+        result
+    }
+
+    #[instrument(skip(self, tasks))]
+    /// Replaces all tasks aborting the currently running ones and spawning the new ones. Tasks
+    /// that are already due are handled by their misfire policy and described in the returned
+    /// messages (see [`ScheduledTasks::hydrate`])
+    pub fn schedule_replace(
+        &self,
+        tasks: ScheduledTasks,
+    ) -> anyhow::Result<(PendingSave, Vec<String>)> {
+        info!("START");
+        let mut guard = self.guard_schedule()?;
+        guard.abort_all();
+        *guard = tasks;
+        let messages = guard.hydrate(self.clone())?;
+        for msg in &messages {
+            warn!(msg);
+        }
+        let pending_save = self.save_scheduled_tasks(&guard)?;
+        info!("END");
+        Ok((pending_save, messages))
+    }
+
+    #[instrument(skip(self))]
+    /// Stops all spawned tasks without changing what is saved so they are spawned again on the
+    /// next start
+    pub fn schedule_abort_all(&self) -> anyhow::Result<()> {
+        info!("START");
+        self.guard_schedule()?.abort_all();
+        info!("END");
+        Ok(())
+    }
+
+    #[instrument(skip(self, history))]
+    /// Replaces the history of tasks that have run
+    pub fn schedule_history_replace(
+        &self,
+        history: ExecutionHistory,
+    ) -> anyhow::Result<PendingSave> {
+        info!("START");
+        let mut guard = self.guard_schedule_history()?;
+        *guard = history;
+        let result = self.save(ExecutionHistory::DATA_KEY, &*guard);
+        info!("END");
+        result
+    }
+
+    #[instrument(skip(self))]
+    /// Adds a task that ran to the history
+    pub fn schedule_history_record(&self, execution: Execution) -> anyhow::Result<()> {
+        let mut guard = self.guard_schedule_history()?;
+        guard.push(execution);
+        self.save(ExecutionHistory::DATA_KEY, &*guard)?;
+        Ok(())
+    }
+
+    /// Returns the page of the history and how many pages there are
+    #[instrument(skip(self))]
+    pub fn schedule_history_page(&self, page: NonZeroUsize) -> anyhow::Result<(String, usize)> {
+        let guard = self.guard_schedule_history()?;
+        Ok((guard.page_as_string(page)?, guard.page_count()))
+    }
+
+    pub fn schedule_len(&self) -> anyhow::Result<usize> {
+        Ok(self.guard_schedule()?.len())
+    }
+
+    #[instrument(skip(self))]
+    pub fn schedule_task_as_string(&self, id: ScheduledTaskId) -> anyhow::Result<String> {
+        let guard = self.guard_schedule()?;
+        Ok(guard.find_task_by_id(id)?.to_string())
     }
 
     #[instrument(skip(self))]
@@ -0,0 +1,299 @@
+//! Rules for tasks that should run again after they fire
+
+use std::{fmt::Display, num::NonZeroU16, str::FromStr};
+
+use anyhow::{Context as _, bail};
+use chrono::{DateTime, Datelike as _, NaiveTime, TimeDelta, Timelike as _, Utc};
+
+use super::UnixTimestamp;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// How far ahead to search for the next match of a cron expression before giving up
+const CRON_SEARCH_LIMIT_DAYS: i64 = 5 * 366;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Repeats this many days after the previous occurrence
+    EveryDays(NonZeroU16),
+
+    /// Repeats at the times matching the expression (evaluated in UTC)
+    Cron(CronSchedule),
+}
+
+impl Recurrence {
+    /// The first occurrence after `previous` that is also after `now` (occurrences missed while
+    /// the bot was offline are skipped)
+    pub fn next_after(
+        &self,
+        previous: UnixTimestamp,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<UnixTimestamp> {
+        match self {
+            Recurrence::EveryDays(days) => {
+                let step = i64::from(days.get()) * SECONDS_PER_DAY;
+                let mut result = previous.0;
+                while result <= now.0 {
+                    result = result
+                        .checked_add(step)
+                        .context("next occurrence is too far in the future")?;
+                }
+                Ok(UnixTimestamp::new(result))
+            }
+            Recurrence::Cron(cron) => cron.next_after(UnixTimestamp::new(previous.0.max(now.0))),
+        }
+    }
+
+    /// Returns the next `count` occurrences after `start`
+    pub fn upcoming(
+        &self,
+        start: UnixTimestamp,
+        count: usize,
+    ) -> anyhow::Result<Vec<UnixTimestamp>> {
+        let mut result = Vec::with_capacity(count);
+        let mut previous = start;
+        for _ in 0..count {
+            previous = self.next_after(previous, previous)?;
+            result.push(previous);
+        }
+        Ok(result)
+    }
+}
+
+/// Accepts either a number of days or a cron expression
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(days) = s.parse::<NonZeroU16>() {
+            return Ok(Self::EveryDays(days));
+        }
+        s.parse().map(Self::Cron).with_context(|| {
+            format!("{s:?} is neither a number of days (1 or more) nor a valid cron expression")
+        })
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::EveryDays(days) if days.get() == 1 => write!(f, "every day"),
+            Recurrence::EveryDays(days) => write!(f, "every {days} days"),
+            Recurrence::Cron(cron) => write!(f, "cron `{cron}` (UTC)"),
+        }
+    }
+}
+
+/// A cron expression with the five fields: minute hour day-of-month month day-of-week.
+///
+/// Each field accepts `*`, single values, ranges (`1-5`), steps (`*/2`, `1-10/3`) and comma
+/// separated lists of those. Day-of-week uses 0 or 7 for Sunday. As in standard cron, if both
+/// day-of-month and day-of-week are restricted a day matching either one is used
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    #[serde(skip)]
+    fields: CronFields,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct CronFields {
+    /// Bit `i` is set if value `i` is allowed
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    fn next_after(&self, after: UnixTimestamp) -> anyhow::Result<UnixTimestamp> {
+        let after = after.to_date_time()?;
+        // Start at the beginning of the next minute
+        let mut candidate =
+            after.with_second(0).expect("0 is a valid second") + TimeDelta::minutes(1);
+        let limit = after + TimeDelta::days(CRON_SEARCH_LIMIT_DAYS);
+        while candidate <= limit {
+            if !self.fields.matches_day(&candidate) {
+                candidate = (candidate.date_naive() + TimeDelta::days(1))
+                    .and_time(NaiveTime::MIN)
+                    .and_utc();
+                continue;
+            }
+            if !is_set(self.fields.hours, candidate.hour()) {
+                candidate =
+                    candidate.with_minute(0).expect("0 is a valid minute") + TimeDelta::hours(1);
+                continue;
+            }
+            if !is_set(self.fields.minutes, candidate.minute()) {
+                candidate += TimeDelta::minutes(1);
+                continue;
+            }
+            return Ok(candidate.into());
+        }
+        bail!(
+            "cron expression `{}` does not match any time in the next {CRON_SEARCH_LIMIT_DAYS} days",
+            self.expression
+        )
+    }
+}
+
+impl CronFields {
+    fn matches_day(&self, date: &DateTime<Utc>) -> bool {
+        if !is_set(self.months, date.month()) {
+            return false;
+        }
+        let day_of_month = is_set(self.days_of_month, date.day());
+        let day_of_week = is_set(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn is_set(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Returns the allowed values as bits and if the field was restricted (does not start with `*`)
+fn parse_field(field: &str, min: u32, max: u32) -> anyhow::Result<(u64, bool)> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .with_context(|| format!("invalid step {step:?}"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // A single value with a step means from that value to the end
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            bail!("range {range:?} is backwards");
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok((bits, !field.starts_with('*')))
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> anyhow::Result<u32> {
+    let result = value
+        .parse::<u32>()
+        .with_context(|| format!("{value:?} is not a number"))?;
+    if !(min..=max).contains(&result) {
+        bail!("{result} is outside of the allowed range {min}-{max}");
+    }
+    Ok(result)
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = parts[..] else {
+            bail!(
+                "expected 5 fields (minute hour day-of-month month day-of-week) but found {}",
+                parts.len()
+            );
+        };
+        let (minutes, _) = parse_field(minutes, 0, 59).context("invalid minute")?;
+        let (hours, _) = parse_field(hours, 0, 23).context("invalid hour")?;
+        let (days_of_month, days_of_month_restricted) =
+            parse_field(days_of_month, 1, 31).context("invalid day of month")?;
+        let (months, _) = parse_field(months, 1, 12).context("invalid month")?;
+        let (mut days_of_week, days_of_week_restricted) =
+            parse_field(days_of_week, 0, 7).context("invalid day of week")?;
+        if is_set(days_of_week, 7) {
+            days_of_week |= 1; // 7 is also Sunday
+        }
+        Ok(Self {
+            expression: parts.join(" "),
+            fields: CronFields {
+                minutes,
+                hours,
+                days_of_month,
+                months,
+                days_of_week,
+                days_of_month_restricted,
+                days_of_week_restricted,
+            },
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(value: CronSchedule) -> Self {
+        value.expression
+    }
+}
+
+impl Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// 2030-01-01 00:00:00 UTC (a Tuesday)
+    const START: i64 = 1_893_456_000;
+
+    #[rstest]
+    #[case::every_day("1", START, START + SECONDS_PER_DAY)]
+    #[case::every_14_days_skips_missed("14", START + 20 * SECONDS_PER_DAY, START + 28 * SECONDS_PER_DAY)]
+    #[case::cron_noon_daily("0 12 * * *", START, START + 12 * 60 * 60)]
+    #[case::cron_monday("30 9 * * 1", START, START + 6 * SECONDS_PER_DAY + 9 * 60 * 60 + 30 * 60)]
+    #[case::cron_sunday_as_7("0 0 * * 7", START, START + 5 * SECONDS_PER_DAY)]
+    #[case::cron_first_of_month("0 0 1 * *", START, 1_896_134_400)]
+    #[case::cron_dom_or_dow("0 0 15 * 4", START, START + 2 * SECONDS_PER_DAY)]
+    fn next_occurrence(#[case] rule: &str, #[case] now: i64, #[case] expected: i64) {
+        let recurrence: Recurrence = rule.parse().unwrap();
+        let actual = recurrence
+            .next_after(UnixTimestamp::new(START), UnixTimestamp::new(now))
+            .unwrap();
+        assert_eq!(actual.0, expected);
+    }
+
+    #[rstest]
+    #[case::zero_days("0")]
+    #[case::too_few_fields("0 12 * *")]
+    #[case::out_of_range("60 * * * *")]
+    #[case::backwards_range("0 5-1 * * *")]
+    #[case::never_matches("0 0 31 2 *")]
+    fn invalid(#[case] rule: &str) {
+        let result = rule.parse::<Recurrence>().and_then(|recurrence| {
+            recurrence.next_after(UnixTimestamp::new(START), UnixTimestamp::new(START))
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! What the scheduled tasks need from the rest of the bot. Kept behind a trait so the scheduler
+//! can be tested without connecting to Discord
+
+use std::{future::Future, time::Duration};
+
+use tracing::{error, warn};
+
+use crate::{
+    Data,
+    clock::Clock,
+    commands::{do_objective, do_start_event_reminder},
+};
+
+use super::{Execution, ExecutionOutcome, Objective, ScheduledTaskId, UnixTimestamp};
+
+pub trait TaskRunner: Clone + Send + Sync + 'static {
+    fn clock(&self) -> &dyn Clock;
+
+    /// How long before a scheduled unranked start each reminder is posted
+    fn reminder_offsets(&self) -> &[Duration];
+
+    fn do_objective(
+        &self,
+        objective: &Objective,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn do_start_event_reminder(
+        &self,
+        starts_at: UnixTimestamp,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Keeps a record of a task that ran
+    fn report_execution(&self, execution: Execution) -> impl Future<Output = ()> + Send;
+
+    /// Removes the task that ran or moves it to its next occurrence. Must not be called while the
+    /// scheduled tasks are locked
+    fn task_fired(&self, id: ScheduledTaskId) -> anyhow::Result<Option<UnixTimestamp>>;
+}
+
+impl TaskRunner for Data {
+    fn clock(&self) -> &dyn Clock {
+        self.inner.shared_config.clock.as_ref()
+    }
+
+    fn reminder_offsets(&self) -> &[Duration] {
+        &self.inner.shared_config.unranked_reminder_offsets
+    }
+
+    async fn do_objective(&self, objective: &Objective) -> anyhow::Result<()> {
+        do_objective(self.inner.ctx.clone(), objective, self).await
+    }
+
+    async fn do_start_event_reminder(&self, starts_at: UnixTimestamp) -> anyhow::Result<()> {
+        do_start_event_reminder(
+            self.inner.ctx.clone(),
+            self.inner.shared_config.channel_unranked,
+            self,
+            starts_at,
+        )
+        .await
+    }
+
+    /// Adds the execution to the history and posts failures in the bot status channel
+    async fn report_execution(&self, execution: Execution) {
+        self.inner.shared_config.metrics.record_task_run(
+            &execution.objective,
+            execution.outcome == ExecutionOutcome::Succeeded,
+        );
+        if matches!(execution.outcome, ExecutionOutcome::Failed { .. }) {
+            match self.inner.shared_config.channel_bot_status {
+                Some(channel) => {
+                    let msg = format!("⚠️ Scheduled task failed\n{execution}");
+                    if let Err(e) = channel.say(&self.inner.ctx, msg).await {
+                        error!("failed to report failed task in bot status channel: {e:?}");
+                    }
+                }
+                None => warn!("Not reporting failed task because channel_bot_status not set"),
+            }
+        }
+        if let Err(e) = self.schedule_history_record(execution) {
+            error!("failed to record task execution in history: {e:?}");
+        }
+    }
+
+    fn task_fired(&self, id: ScheduledTaskId) -> anyhow::Result<Option<UnixTimestamp>> {
+        self.schedule_task_fired(id)
+    }
+}
@@ -0,0 +1,295 @@
+//! Turns what people type for a date/time into a timestamp.
+//!
+//! Accepted formats (case insensitive):
+//! - A unix timestamp or a discord timestamp tag: `1893456000`, `<t:1893456000:F>` (at least 10
+//!   digits so that a number like `2030` is not mistaken for a time in 1970)
+//! - A date with an optional time: `2026-11-01 18:00`, `2026-11-01T18:00:30`, `2026-11-01`
+//! - A relative time: `in 3 days 2h`, `in 90 minutes`
+//! - A day with an optional time: `next friday 20:00`, `friday 8pm`, `tomorrow 18:30`, `today 9:15pm`
+//!
+//! Absolute dates and days can end with a timezone (`UTC`, `GMT`, `Z` or an offset like `+02:00`
+//! or `-0500`) and are taken to be in the alliance timezone otherwise
+
+use anyhow::{Context as _, bail};
+use chrono::{
+    DateTime, Datelike as _, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use super::UnixTimestamp;
+
+pub const FORMATS_HELP: &str = "Accepted formats:
+- A unix timestamp (eg. `1893456000`) or discord timestamp (eg. `<t:1893456000:F>`)
+- A date and time (eg. `2026-11-01 18:00 UTC`)
+- A relative time (eg. `in 3 days 2h`)
+- A day and time (eg. `next friday 20:00`, `tomorrow 8pm`)
+Times are in the alliance timezone unless `UTC` or an offset like `+02:00` is added at the end";
+
+/// The smallest number accepted as a unix timestamp (2001-09-09), anything smaller is more likely
+/// to be a mistake (eg. a year) than a time in the past
+const MIN_UNIX_TIMESTAMP: i64 = 1_000_000_000;
+
+/// Returns the timestamp described by `input` (relative formats are relative to `now`). Times
+/// without a timezone are taken to be in `timezone`
+pub fn parse(input: &str, now: DateTime<Utc>, timezone: Tz) -> anyhow::Result<UnixTimestamp> {
+    let input = input.trim().to_lowercase();
+    let result = if let Some(timestamp) = parse_unix(&input) {
+        Ok(timestamp)
+    } else if let Some(rest) = input.strip_prefix("in ") {
+        parse_relative(rest, now)
+    } else {
+        parse_with_timezone(&input, now, timezone)
+    };
+    result
+        .with_context(|| format!("unable to understand {input:?} as a date/time.\n{FORMATS_HELP}"))
+}
+
+fn parse_unix(input: &str) -> Option<UnixTimestamp> {
+    let value = match input.strip_prefix("<t:") {
+        Some(rest) => rest.trim_end_matches('>').split(':').next()?,
+        None => input,
+    };
+    value
+        .parse()
+        .ok()
+        .filter(|value| *value >= MIN_UNIX_TIMESTAMP)
+        .map(UnixTimestamp::new)
+}
+
+fn parse_relative(input: &str, now: DateTime<Utc>) -> anyhow::Result<UnixTimestamp> {
+    let mut total = TimeDelta::zero();
+    let mut found_any = false;
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        // Allow separators between parts like in "2 days, 3 hours and 5 minutes"
+        if let Some(after) = rest.strip_prefix(',').or_else(|| rest.strip_prefix("and ")) {
+            rest = after.trim_start();
+            continue;
+        }
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: i64 = rest[..digits_end]
+            .parse()
+            .with_context(|| format!("expected a number at {rest:?}"))?;
+        rest = rest[digits_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_end];
+        rest = rest[unit_end..].trim_start();
+        let part = match unit {
+            "s" | "sec" | "secs" | "second" | "seconds" => TimeDelta::try_seconds(amount),
+            "m" | "min" | "mins" | "minute" | "minutes" => TimeDelta::try_minutes(amount),
+            "h" | "hr" | "hrs" | "hour" | "hours" => TimeDelta::try_hours(amount),
+            "d" | "day" | "days" => TimeDelta::try_days(amount),
+            "w" | "week" | "weeks" => TimeDelta::try_weeks(amount),
+            _ => bail!("unknown unit of time {unit:?}"),
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .context("relative time is too large")?;
+        found_any = true;
+    }
+    if !found_any {
+        bail!("no amount of time found after \"in\"");
+    }
+    Ok((now + total).into())
+}
+
+fn parse_with_timezone(
+    input: &str,
+    now: DateTime<Utc>,
+    timezone: Tz,
+) -> anyhow::Result<UnixTimestamp> {
+    match split_timezone(input)? {
+        (input, Some(offset)) => parse_in(input, now, &offset),
+        (input, None) => parse_in(input, now, &timezone),
+    }
+}
+
+/// Parses an absolute date or a day as a local time in `zone`
+fn parse_in<Z: TimeZone>(
+    input: &str,
+    now: DateTime<Utc>,
+    zone: &Z,
+) -> anyhow::Result<UnixTimestamp> {
+    let naive = match parse_date_time(input) {
+        Some(naive) => naive,
+        None => parse_day(input, now.with_timezone(zone).naive_local())?,
+    };
+    // Times repeated when the clocks go back use the first one
+    let date_time = zone.from_local_datetime(&naive).earliest().context(
+        "that time does not exist in the timezone (skipped by a daylight saving change)",
+    )?;
+    Ok(date_time.with_timezone(&Utc).into())
+}
+
+/// Removes the timezone from the end of the input if there is one (`None` if there isn't)
+fn split_timezone(input: &str) -> anyhow::Result<(&str, Option<FixedOffset>)> {
+    let utc = FixedOffset::east_opt(0).expect("0 is a valid offset");
+    let Some((rest, last)) = input.rsplit_once(' ') else {
+        return Ok((input, None));
+    };
+    if matches!(last, "utc" | "gmt" | "z") {
+        return Ok((rest.trim_end(), Some(utc)));
+    }
+    let (sign, offset) = if let Some(x) = last.strip_prefix('+') {
+        (1, x)
+    } else if let Some(x) = last.strip_prefix('-') {
+        (-1, x)
+    } else {
+        return Ok((input, None));
+    };
+    let digits = offset.replace(':', "");
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.as_str(), "0"),
+        4 => digits.split_at(2),
+        _ => bail!("invalid timezone offset {last:?}"),
+    };
+    let seconds = (hours.parse::<i32>()? * 60 + minutes.parse::<i32>()?) * 60;
+    let offset = FixedOffset::east_opt(sign * seconds)
+        .with_context(|| format!("invalid timezone offset {last:?}"))?;
+    Ok((rest.trim_end(), Some(offset)))
+}
+
+fn parse_date_time(input: &str) -> Option<NaiveDateTime> {
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dt%H:%M",
+        "%Y-%m-%dt%H:%M:%S",
+    ];
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+}
+
+/// Parses a day (`today`, `tomorrow` or a weekday optionally after `next`) with an optional time.
+/// Weekdays are the next time that day and time come around after `now`
+fn parse_day(input: &str, now: NaiveDateTime) -> anyhow::Result<NaiveDateTime> {
+    let input = input.strip_prefix("next ").unwrap_or(input).trim_start();
+    let (day, time) = input.split_once(' ').unwrap_or((input, ""));
+    let time = if time.trim().is_empty() {
+        NaiveTime::MIN
+    } else {
+        parse_time(time.trim())?
+    };
+    let today = now.date();
+    match day {
+        "today" => Ok(today.and_time(time)),
+        "tomorrow" => Ok(today
+            .succ_opt()
+            .context("date out of range")?
+            .and_time(time)),
+        _ => {
+            let weekday: Weekday = day
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{day:?} is not a day of the week"))?;
+            let days_ahead =
+                (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+            let mut result = (today + TimeDelta::days(days_ahead.into())).and_time(time);
+            if result <= now {
+                result += TimeDelta::weeks(1);
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// Accepts 24 hour times (`20:00`, `20`) and 12 hour times (`8pm`, `8:30 pm`)
+fn parse_time(input: &str) -> anyhow::Result<NaiveTime> {
+    let input = input.replace(' ', "");
+    let (input, pm) = if let Some(x) = input.strip_suffix("pm") {
+        (x, Some(true))
+    } else if let Some(x) = input.strip_suffix("am") {
+        (x, Some(false))
+    } else {
+        (input.as_str(), None)
+    };
+    let (hours, minutes) = input.split_once(':').unwrap_or((input, "0"));
+    let mut hours: u32 = hours
+        .parse()
+        .with_context(|| format!("{hours:?} is not a valid hour"))?;
+    let minutes: u32 = minutes
+        .parse()
+        .with_context(|| format!("{minutes:?} is not a valid minute"))?;
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&hours) {
+            bail!("{hours} is not a valid hour for am/pm");
+        }
+        hours = hours % 12 + if pm { 12 } else { 0 };
+    }
+    NaiveTime::from_hms_opt(hours, minutes, 0)
+        .with_context(|| format!("{input:?} is not a valid time"))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// 2030-01-01 00:00:00 UTC (a Tuesday)
+    const NOW: i64 = 1_893_456_000;
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    #[rstest]
+    #[case::unix("1893456000", 0)]
+    #[case::discord_tag("<t:1893456000:F>", 0)]
+    #[case::date_time("2030-01-02 18:00 UTC", DAY + 18 * HOUR)]
+    #[case::date_time_no_tz("2030-01-02T18:00", DAY + 18 * HOUR)]
+    #[case::date_only("2030-01-03", 2 * DAY)]
+    #[case::date_time_offset("2030-01-02 18:00 +02:00", DAY + 16 * HOUR)]
+    #[case::date_time_negative_offset("2030-01-02 18:00 -0500", DAY + 23 * HOUR)]
+    #[case::relative("in 3 days 2h", 3 * DAY + 2 * HOUR)]
+    #[case::relative_separators("in 1 day, 2 hours and 30 minutes", DAY + 2 * HOUR + 30 * 60)]
+    #[case::next_weekday("next friday 20:00", 3 * DAY + 20 * HOUR)]
+    #[case::weekday_pm("Friday 8pm", 3 * DAY + 20 * HOUR)]
+    #[case::same_weekday_is_next_week("tuesday", 7 * DAY)]
+    #[case::same_weekday_later_today("tuesday 9:30am", 9 * HOUR + 30 * 60)]
+    #[case::tomorrow("tomorrow 18:30", DAY + 18 * HOUR + 30 * 60)]
+    #[case::today_offset("today 12am +01:00", -HOUR)]
+    fn valid(#[case] input: &str, #[case] seconds_from_now: i64) {
+        let now = DateTime::from_timestamp(NOW, 0).unwrap();
+        let actual = parse(input, now, Tz::UTC).unwrap();
+        assert_eq!(actual.0, NOW + seconds_from_now);
+    }
+
+    /// In New York it is still Monday 2029-12-31 19:00 (UTC-5) at `NOW`
+    #[rstest]
+    #[case::date_time("2030-01-02 18:00", DAY + 23 * HOUR)]
+    #[case::weekday("friday 20:00", 4 * DAY + HOUR)]
+    #[case::today("today 20:00", HOUR)]
+    #[case::explicit_utc("2030-01-02 18:00 utc", DAY + 18 * HOUR)]
+    #[case::explicit_offset("2030-01-02 18:00 +02:00", DAY + 16 * HOUR)]
+    #[case::relative("in 2h", 2 * HOUR)]
+    #[case::unix("1893456000", 0)]
+    fn defaults_to_alliance_timezone(#[case] input: &str, #[case] seconds_from_now: i64) {
+        let now = DateTime::from_timestamp(NOW, 0).unwrap();
+        let actual = parse(input, now, Tz::America__New_York).unwrap();
+        assert_eq!(actual.0, NOW + seconds_from_now);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::nonsense("whenever")]
+    #[case::bad_unit("in 3 fortnights")]
+    #[case::bad_time("friday 25:00")]
+    #[case::bad_offset("2030-01-02 18:00 +123")]
+    #[case::year_only("2030")]
+    #[case::small_number("20")]
+    #[case::small_discord_tag("<t:20:F>")]
+    #[case::skipped_by_daylight_saving("2030-03-10 02:30")]
+    fn invalid(#[case] input: &str) {
+        let now = DateTime::from_timestamp(NOW, 0).unwrap();
+        assert!(parse(input, now, Tz::America__New_York).is_err());
+    }
+}
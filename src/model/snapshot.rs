@@ -0,0 +1,138 @@
+//! A copy of all the stored data in one document so it can be downloaded and kept as a backup
+
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use tracing::info;
+
+use crate::{
+    Data,
+    config::SharedConfig,
+    model::{
+        schedule::{ScheduledTasks, UnixTimestamp},
+        unranked::{ideas::Ideas, scores::Scores},
+        versioned::{self, Upgrade, Versioned},
+    },
+};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub bot_version: String,
+    pub created_at: UnixTimestamp,
+    /// The content of each key as it was stored (including its own version envelope)
+    pub entries: BTreeMap<String, serde_json::Value>,
+}
+
+/// The values from a snapshot converted into the current model types
+pub struct SnapshotData {
+    pub ideas: Ideas,
+    pub scores: Scores,
+    pub scheduled_tasks: ScheduledTasks,
+    /// Keys in the snapshot that are not restored (for example the heartbeat)
+    pub skipped_keys: Vec<String>,
+}
+
+impl Versioned for Snapshot {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
+impl Snapshot {
+    /// Collects the current stored value of every key (waits for pending saves first)
+    pub async fn create(shared_config: &SharedConfig) -> anyhow::Result<Self> {
+        shared_config
+            .flush_kv()
+            .await
+            .context("failed to flush pending saves before taking snapshot")?;
+        let mut entries = BTreeMap::new();
+        for key in shared_config.kv_keys()? {
+            let Some(content) = shared_config.load_raw_kv(&key)? else {
+                continue;
+            };
+            let value = serde_json::from_str(&content)
+                .with_context(|| format!("stored content for key: {key} is not valid json"))?;
+            entries.insert(key, value);
+        }
+        let result = Self {
+            bot_version: version::version!().to_string(),
+            created_at: shared_config.clock.now()?,
+            entries,
+        };
+        info!(
+            "Snapshot created with keys: {:?}",
+            result.entries.keys().collect::<Vec<_>>()
+        );
+        Ok(result)
+    }
+
+    /// Converts the entries into the model types (upgrading them if needed).
+    /// Keys that are missing from the snapshot are taken to be empty
+    pub fn to_data(&self) -> anyhow::Result<SnapshotData> {
+        fn convert<T: Versioned + Default>(snapshot: &Snapshot, key: &str) -> anyhow::Result<T> {
+            match snapshot.entries.get(key) {
+                Some(value) => versioned::from_value(value.clone())
+                    .with_context(|| format!("invalid value for key: {key}")),
+                None => Ok(T::default()),
+            }
+        }
+        let restored_keys = [Ideas::DATA_KEY, Scores::DATA_KEY, ScheduledTasks::DATA_KEY];
+        Ok(SnapshotData {
+            ideas: convert(self, Ideas::DATA_KEY)?,
+            scores: convert(self, Scores::DATA_KEY)?,
+            scheduled_tasks: convert(self, ScheduledTasks::DATA_KEY)?,
+            skipped_keys: self
+                .entries
+                .keys()
+                .filter(|key| !restored_keys.contains(&key.as_str()))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("bazooka-bot-snapshot-{}.json", self.created_at.0)
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        versioned::to_json_pretty(self)
+    }
+}
+
+impl Data {
+    /// Replaces the current ideas, scores and scheduled tasks with the ones from the snapshot.
+    /// Returns what happened to the scheduled tasks that were already due
+    pub fn restore_snapshot(&self, data: SnapshotData) -> anyhow::Result<Vec<String>> {
+        info!("START");
+        let SnapshotData {
+            ideas,
+            scores,
+            scheduled_tasks,
+            skipped_keys,
+        } = data;
+        self.inner.unranked.ideas_replace(ideas)?;
+        self.inner.unranked.scores_replace(scores)?;
+        let (_, messages) = self.schedule_replace(scheduled_tasks)?;
+        info!("END. Skipped keys: {skipped_keys:?}");
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::clock::TestClock;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn created_at_comes_from_the_clock() {
+        let clock = Arc::new(TestClock::new(UnixTimestamp::new(1_893_456_000)));
+        let shared_config = SharedConfig::new_for_test(clock);
+        shared_config
+            .save_kv(Ideas::DATA_KEY, &Ideas::default())
+            .unwrap();
+        let snapshot = Snapshot::create(shared_config).await.unwrap();
+        assert_eq!(snapshot.created_at.0, 1_893_456_000);
+        assert!(snapshot.entries.contains_key(Ideas::DATA_KEY));
+    }
+}
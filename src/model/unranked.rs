@@ -2,30 +2,43 @@
 
 use crate::{
     config::SharedConfig,
-    model::unranked::{ideas::Ideas, scores::Scores},
+    db::PendingSave,
+    model::{
+        unranked::{ideas::Ideas, scores::Scores, start_event_undo::StartEventUndo},
+        versioned::Versioned,
+    },
 };
 use std::sync::{Arc, Mutex};
 
 pub mod ideas;
 pub mod scores;
+pub mod start_event_preview;
+pub mod start_event_undo;
 
 pub struct Unranked {
     ideas: Arc<Mutex<Ideas>>,
     scores: Arc<Mutex<Scores>>,
+    start_event_undo: Arc<Mutex<StartEventUndo>>,
     shared_config: &'static SharedConfig,
 }
 impl Unranked {
-    pub async fn new(shared_config: &'static SharedConfig) -> Self {
-        let ideas = Arc::new(Mutex::new(Ideas::new(shared_config).await));
-        let scores = Arc::new(Mutex::new(Scores::new(shared_config).await));
-        Self {
+    pub async fn new(shared_config: &'static SharedConfig) -> anyhow::Result<Self> {
+        let ideas = Ideas::new(shared_config).await?;
+        let scores = Scores::new(shared_config).await?;
+        shared_config.metrics.set_idea_count(ideas.len());
+        shared_config.metrics.set_score_count(scores.len());
+        let ideas = Arc::new(Mutex::new(ideas));
+        let scores = Arc::new(Mutex::new(scores));
+        let start_event_undo = Arc::new(Mutex::new(StartEventUndo::new(shared_config).await?));
+        Ok(Self {
             ideas,
             scores,
+            start_event_undo,
             shared_config,
-        }
+        })
     }
 
-    fn save<T: serde::Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> {
+    fn save<T: Versioned>(&self, key: &str, value: &T) -> anyhow::Result<PendingSave> {
         self.shared_config.save_kv(key, value)
     }
 }
@@ -1,6 +1,11 @@
 use crate::{
     config::SharedConfig,
-    model::{one_based_id::OneBasedId, user_serde::UserIdNumber},
+    model::{
+        one_based_id::OneBasedId,
+        user_serde::UserIdNumber,
+        versioned::{self, Upgrade, Versioned},
+    },
+    user_error::UserError,
 };
 use anyhow::{Context as _, bail};
 use poise::serenity_prelude::CacheHttp;
@@ -109,7 +114,7 @@ impl Display for Ideas {
 }
 
 impl Ideas {
-    const DATA_KEY: &'static str = "ideas";
+    pub const DATA_KEY: &'static str = "ideas";
     pub const DISPLAY_TITLE: &'static str = "# Unranked Ideas";
     const DEFAULT_DISCARD_THRESHOLD: usize = 2;
     pub fn add(&mut self, user_id_number: UserIdNumber, description: String) {
@@ -117,6 +122,14 @@ impl Ideas {
         self.data.push(value);
     }
 
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub async fn verbose_display(&self, cache_http: impl CacheHttp) -> anyhow::Result<String> {
         use std::fmt::Write as _;
         let mut result = String::new();
@@ -173,7 +186,9 @@ impl Ideas {
                 "Request to edit Idea# {id} by user# {user_id_number} but it was created by {}",
                 idea.creator.to_user_id()
             );
-            bail!("Failed to edit Idea# {id} because you didn't create it.")
+            bail!(UserError::new(format!(
+                "Failed to edit Idea# {id} because you didn't create it."
+            )))
         }
 
         info!(
@@ -201,7 +216,9 @@ impl Ideas {
                 "Request to remove Idea# {id} by user {user_id_number} but it was created by {}",
                 idea.creator.to_user_id()
             );
-            bail!("Failed to remove Idea# {id} because you didn't create it.")
+            bail!(UserError::new(format!(
+                "Failed to remove Idea# {id} because you didn't create it."
+            )))
         }
 
         // Action the removal
@@ -247,17 +264,18 @@ impl Ideas {
     }
 
     fn err_invalid_id(&self, id: IdeaId) -> anyhow::Error {
-        anyhow::format_err!(
+        UserError::new(format!(
             "ID: {id} is not a valid ID. {}",
             if self.data.is_empty() {
                 "There are NO ideas.".to_string()
             } else {
                 format!("Valid IDs are 1..{}", self.data.len())
             }
-        )
+        ))
+        .into()
     }
 
-    pub async fn new(shared_config: &SharedConfig) -> Self {
+    pub async fn new(shared_config: &SharedConfig) -> anyhow::Result<Self> {
         shared_config.load_or_default_kv(Self::DATA_KEY).await
     }
 
@@ -282,13 +300,35 @@ impl Ideas {
         Some(result)
     }
 
+    /// The number of ideas that would remain after [`Self::reset_with_threshold`]
+    pub fn count_kept_on_reset(&self) -> usize {
+        self.data
+            .iter()
+            .filter(|idea| idea.voters.len() > self.discard_threshold)
+            .count()
+    }
+
+    /// Returns copies of the ideas that [`Self::reset_with_threshold`] would keep and discard (in that
+    /// order) without changing anything. Both keep the order the reset would leave them in
+    pub fn split_on_reset(&self) -> (Vec<Idea>, Vec<Idea>) {
+        let mut data = self.data.clone();
+        Self::sort_by_votes(&mut data);
+        data.into_iter()
+            .partition(|idea| idea.voters.len() > self.discard_threshold)
+    }
+
+    /// Sorts by vote count (highest first) keeping ideas with the same count in the order they appeared
+    fn sort_by_votes(data: &mut [Idea]) {
+        data.sort_by_key(|idea| -(idea.voters.len() as i32)); // Rev wouldn't work because we need to keep them in inserted order
+    }
+
     /// Discards all ideas at or below the threshold and clears the votes of the remaining ideas
     /// The order of the ideas after reset is guaranteed to be sorted by their previously vote counts
     /// and still in the order they appeared otherwise. The previously leading ideas is guaranteed to
     // be the first one if it existed
     pub fn reset_with_threshold(&mut self) {
         // Sort ideas in required order (see doc string)
-        self.data.sort_by_key(|idea| -(idea.voters.len() as i32)); // Rev wouldn't work because we need to keep them in inserted order
+        Self::sort_by_votes(&mut self.data);
 
         // Remove ideas at or below the line resetting the votes on the rest
         self.data.retain_mut(|idea| {
@@ -311,6 +351,10 @@ impl Default for Ideas {
     }
 }
 
+impl Versioned for Ideas {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
 #[cfg(test)]
 mod tests {
     use poise::serenity_prelude::UserId;
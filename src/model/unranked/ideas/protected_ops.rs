@@ -5,18 +5,29 @@ use std::sync::MutexGuard;
 
 use poise::serenity_prelude::CacheHttp;
 
-use crate::model::{unranked::Unranked, user_serde::UserIdNumber};
+use crate::{
+    db::PendingSave,
+    model::{unranked::Unranked, user_serde::UserIdNumber},
+};
 
 use super::{Idea, IdeaId, Ideas};
 
 impl Unranked {
-    fn guard_idea(&'_ self) -> anyhow::Result<MutexGuard<'_, Ideas>> {
+    /// Serves as the link to the private function that returns the guard
+    pub(in crate::model::unranked) fn guard_idea(
+        &'_ self,
+    ) -> anyhow::Result<MutexGuard<'_, Ideas>> {
         match self.ideas.lock() {
             Ok(guard) => Ok(guard),
             Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
         }
     }
-    fn save_idea(&self, data: &Ideas) -> anyhow::Result<()> {
+    /// Also keeps the idea count metric up to date
+    pub(in crate::model::unranked) fn save_idea(
+        &self,
+        data: &Ideas,
+    ) -> anyhow::Result<PendingSave> {
+        self.shared_config.metrics.set_idea_count(data.len());
         self.save(Ideas::DATA_KEY, data)
     }
 
@@ -110,6 +121,23 @@ impl Unranked {
         Ok(())
     }
 
+    pub fn ideas_len(&self) -> anyhow::Result<usize> {
+        Ok(self.guard_idea()?.len())
+    }
+
+    /// Returns the total number of ideas and how many would be kept by a reset
+    pub fn ideas_reset_preview(&self) -> anyhow::Result<(usize, usize)> {
+        let guard = self.guard_idea()?;
+        Ok((guard.len(), guard.count_kept_on_reset()))
+    }
+
+    /// Replaces all ideas (including the threshold)
+    pub fn ideas_replace(&self, ideas: Ideas) -> anyhow::Result<PendingSave> {
+        let mut guard = self.guard_idea()?;
+        *guard = ideas;
+        self.save_idea(&guard)
+    }
+
     /// Removes and returns the leading idea if one exists
     pub fn ideas_pop_leading(&self) -> anyhow::Result<Option<Idea>> {
         let mut guard = self.guard_idea()?;
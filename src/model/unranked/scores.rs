@@ -6,7 +6,10 @@ use tracing::{error, info};
 use crate::{
     RemoveElement as _, Resettable,
     config::SharedConfig,
-    model::user_serde::{UserIdNumber, UserName, UserRecord},
+    model::{
+        user_serde::{UserIdNumber, UserName, UserRecord},
+        versioned::{self, Upgrade, Versioned},
+    },
 };
 
 pub mod protected_ops;
@@ -17,7 +20,7 @@ type ScoresCache = BTreeMap<ScoreValue, Vec<UserName>>;
 /// Users scores
 ///
 /// Assumes that each user has at most one record
-#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
 pub struct Scores {
     pub message: String,
     records: Vec<ScoreRecord>,
@@ -33,7 +36,7 @@ pub struct ScoreRecord {
 
 impl Scores {
     pub const DISPLAY_TITLE: &'static str = "UNRANKED CHALLENGE";
-    const DATA_KEY: &'static str = "scores";
+    pub const DATA_KEY: &'static str = "scores";
     pub fn set_score(&mut self, user: UserRecord, score: ScoreValue) -> anyhow::Result<()> {
         // Generate cache if it doesn't exist so that the code later can assume it already exists for the current data
         self.cache()?;
@@ -119,6 +122,14 @@ impl Scores {
             .expect("value should have just been set if it didn't exist"))
     }
 
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
     /// Removes the score if it exists and returns true iff the score was removed
     pub fn remove_score(&mut self, user: &UserRecord) -> anyhow::Result<bool> {
         // Generate cache if it doesn't exist so that the code later can assume it already exists for the current data
@@ -163,9 +174,13 @@ impl Scores {
         self.message = msg;
     }
 
-    pub async fn new(shared_config: &SharedConfig) -> Self {
+    pub async fn new(shared_config: &SharedConfig) -> anyhow::Result<Self> {
         shared_config.load_or_default_kv(Self::DATA_KEY).await
     }
 }
 
 impl Resettable for Scores {}
+
+impl Versioned for Scores {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
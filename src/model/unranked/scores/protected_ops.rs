@@ -5,6 +5,7 @@ use std::sync::MutexGuard;
 
 use crate::{
     Resettable as _,
+    db::PendingSave,
     model::{
         unranked::Unranked,
         user_serde::{UserIdNumber, UserRecord},
@@ -15,14 +16,21 @@ use super::{ScoreValue, Scores};
 
 impl Unranked {
     /// Serves as the link to the private function that returns the guard
-    fn guard_scores(&'_ self) -> anyhow::Result<MutexGuard<'_, Scores>> {
+    pub(in crate::model::unranked) fn guard_scores(
+        &'_ self,
+    ) -> anyhow::Result<MutexGuard<'_, Scores>> {
         match self.scores.lock() {
             Ok(guard) => Ok(guard),
             Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
         }
     }
 
-    fn save_scores(&self, data: &Scores) -> anyhow::Result<()> {
+    /// Also keeps the score count metric up to date
+    pub(in crate::model::unranked) fn save_scores(
+        &self,
+        data: &Scores,
+    ) -> anyhow::Result<PendingSave> {
+        self.shared_config.metrics.set_score_count(data.len());
         self.save(Scores::DATA_KEY, data)
     }
 
@@ -61,4 +69,14 @@ impl Unranked {
         self.save_scores(&guard)?;
         Ok(())
     }
+
+    pub fn scores_len(&self) -> anyhow::Result<usize> {
+        Ok(self.guard_scores()?.len())
+    }
+
+    pub fn scores_replace(&self, scores: Scores) -> anyhow::Result<PendingSave> {
+        let mut guard = self.guard_scores()?;
+        *guard = scores;
+        self.save_scores(&guard)
+    }
 }
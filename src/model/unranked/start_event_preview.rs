@@ -0,0 +1,80 @@
+//! Works out what starting a new unranked event would do without changing anything
+
+use crate::{
+    Resettable as _,
+    model::unranked::{
+        ideas::{Idea, Ideas},
+        scores::Scores,
+    },
+};
+
+pub mod protected_ops;
+
+/// The outcome of starting a new unranked event, computed from copies of the current data
+#[derive(Debug)]
+pub struct StartEventPreview {
+    /// The idea that becomes the challenge for the new event
+    pub winner: Option<Idea>,
+    /// Ideas that stay for the next vote (their votes get cleared)
+    pub surviving: Vec<Idea>,
+    /// Ideas at or below the threshold that get removed
+    pub discarded: Vec<Idea>,
+    pub discard_threshold: usize,
+    /// Number of score records that get removed
+    pub scores_cleared: usize,
+    /// How the scores will be displayed once the event has started
+    pub new_scores: String,
+}
+
+impl StartEventPreview {
+    pub fn new(mut ideas: Ideas, mut scores: Scores) -> anyhow::Result<Self> {
+        let winner = ideas.pop_leading();
+        let (surviving, discarded) = ideas.split_on_reset();
+        let scores_cleared = scores.len();
+        scores.reset();
+        scores.message = challenge_message(winner.as_ref());
+        Ok(Self {
+            winner,
+            surviving,
+            discarded,
+            discard_threshold: ideas.discard_threshold,
+            scores_cleared,
+            new_scores: scores.display()?,
+        })
+    }
+}
+
+/// The scores message used for the new event
+pub fn challenge_message(winner: Option<&Idea>) -> String {
+    match winner {
+        Some(idea) => idea.description.clone(),
+        None => "Seems there were no ideas".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_matches_start_event() {
+        let ideas: Ideas = (
+            vec![
+                ("low", vec![1]),
+                ("winner", vec![1, 2, 3, 4]),
+                ("kept", vec![1, 2, 3]),
+            ],
+            2,
+        )
+            .into();
+        let preview = StartEventPreview::new(ideas, Scores::default()).unwrap();
+        let descriptions = |ideas: &[Idea]| -> Vec<String> {
+            ideas.iter().map(|x| x.description.clone()).collect()
+        };
+
+        assert_eq!(preview.winner.unwrap().description, "winner");
+        assert_eq!(descriptions(&preview.surviving), ["kept"]);
+        assert_eq!(descriptions(&preview.discarded), ["low"]);
+        assert!(preview.new_scores.starts_with("winner"));
+    }
+}
@@ -0,0 +1,24 @@
+//! This module exists to make it harder to get deadlocks by grouping functions that MUST NOT call each other.
+//! Locks ideas then scores (same order as the start event undo) but only long enough to copy them
+
+use tracing::{info, instrument};
+
+use crate::model::unranked::Unranked;
+
+use super::StartEventPreview;
+
+impl Unranked {
+    /// Computes what starting a new event would do right now without changing anything
+    #[instrument(skip(self))]
+    pub fn start_event_preview(&self) -> anyhow::Result<StartEventPreview> {
+        info!("START");
+        let (ideas, scores) = {
+            let ideas = self.guard_idea()?;
+            let scores = self.guard_scores()?;
+            (ideas.clone(), scores.clone())
+        };
+        let result = StartEventPreview::new(ideas, scores);
+        info!("END");
+        result
+    }
+}
@@ -0,0 +1,134 @@
+//! Keeps the ideas and scores from just before the last start event so that it can be undone
+
+use std::time::Duration;
+
+use anyhow::bail;
+use human_time::ToHumanTimeString as _;
+
+use crate::{
+    model::{
+        schedule::UnixTimestamp,
+        unranked::{ideas::Ideas, scores::Scores},
+        versioned::{self, Upgrade, Versioned},
+    },
+    user_error::UserError,
+};
+
+pub mod protected_ops;
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct StartEventUndo {
+    /// `None` if there is nothing to undo
+    pre_event: Option<PreEventSnapshot>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PreEventSnapshot {
+    taken_at: UnixTimestamp,
+    ideas: Ideas,
+    scores: Scores,
+}
+
+impl StartEventUndo {
+    pub const DATA_KEY: &'static str = "start_event_undo";
+
+    pub async fn new(shared_config: &crate::SharedConfig) -> anyhow::Result<Self> {
+        shared_config.load_or_default_kv(Self::DATA_KEY).await
+    }
+
+    fn set(&mut self, taken_at: UnixTimestamp, ideas: Ideas, scores: Scores) {
+        self.pre_event = Some(PreEventSnapshot {
+            taken_at,
+            ideas,
+            scores,
+        });
+    }
+
+    /// Removes and returns the snapshot if there is one
+    fn take(&mut self) -> Option<(UnixTimestamp, Ideas, Scores)> {
+        self.pre_event
+            .take()
+            .map(|snapshot| (snapshot.taken_at, snapshot.ideas, snapshot.scores))
+    }
+
+    fn taken_at(&self) -> Option<UnixTimestamp> {
+        self.pre_event.as_ref().map(|snapshot| snapshot.taken_at)
+    }
+
+    /// Fails if the snapshot was taken more than `grace` before `now`. A snapshot from the future
+    /// (clock changed or an old snapshot restored) cannot be trusted so it is treated as expired
+    fn check_within_grace(
+        taken_at: UnixTimestamp,
+        now: UnixTimestamp,
+        grace: Duration,
+    ) -> anyhow::Result<()> {
+        match now.duration_since(taken_at) {
+            Some(age) if age <= grace => Ok(()),
+            Some(_) => bail!(UserError::new(format!(
+                "The last start event was at {taken_at} and can only be undone within {} of starting",
+                grace.to_human_time_string()
+            ))),
+            None => bail!(UserError::new(format!(
+                "The last start event is recorded as being in the future ({taken_at}) so it cannot be undone"
+            ))),
+        }
+    }
+}
+
+impl Versioned for StartEventUndo {
+    const UPGRADES: &'static [Upgrade] = &[versioned::from_unversioned];
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use poise::serenity_prelude::UserId;
+    use rstest::rstest;
+
+    use crate::{SharedConfig, clock::TestClock, model::unranked::Unranked};
+
+    use super::*;
+
+    const NOW: i64 = 1_893_456_000;
+    const HOUR: i64 = 60 * 60;
+
+    #[rstest]
+    #[case::within(NOW - HOUR, true)]
+    #[case::at_limit(NOW - 2 * HOUR, true)]
+    #[case::expired(NOW - 3 * HOUR, false)]
+    #[case::in_the_future(NOW + HOUR, false)]
+    fn grace_period(#[case] taken_at: i64, #[case] expected: bool) {
+        let actual = StartEventUndo::check_within_grace(
+            UnixTimestamp::new(taken_at),
+            UnixTimestamp::new(NOW),
+            Duration::from_secs(2 * HOUR as u64),
+        );
+        assert_eq!(actual.is_ok(), expected, "{actual:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn undo_uses_the_clock() {
+        let clock = Arc::new(TestClock::new(UnixTimestamp::new(NOW)));
+        let shared_config = SharedConfig::new_for_test(clock);
+        let unranked = Unranked::new(shared_config).await.unwrap();
+        let grace = Duration::from_secs(2 * HOUR as u64);
+        unranked
+            .idea_add(UserId::new(1).into(), "Only idea".to_string())
+            .unwrap();
+        unranked.start_event_save_undo().unwrap();
+        unranked.ideas_pop_leading().unwrap();
+        assert_eq!(unranked.ideas_len().unwrap(), 0);
+
+        tokio::time::sleep(grace).await;
+        assert_eq!(unranked.start_event_undo(grace).unwrap().0, NOW);
+        assert_eq!(unranked.ideas_len().unwrap(), 1);
+        let metrics = shared_config.metrics.encode().unwrap();
+        assert!(metrics.contains("bazooka_ideas 1"), "{metrics}");
+
+        unranked.start_event_save_undo().unwrap();
+        tokio::time::sleep(grace + Duration::from_secs(1)).await;
+        let err = unranked.start_event_undo(grace).unwrap_err();
+        assert!(UserError::is_cause_of(&err), "{err:?}");
+    }
+}
@@ -0,0 +1,73 @@
+//! This module exists to make it harder to get deadlocks by grouping functions that MUST NOT call each other.
+//! The functions here need both ideas and scores to change together so they always lock
+//! ideas, then scores, then the undo snapshot (in that order)
+
+use std::{sync::MutexGuard, time::Duration};
+
+use anyhow::bail;
+use tracing::{info, instrument};
+
+use crate::{
+    db::PendingSave,
+    model::{schedule::UnixTimestamp, unranked::Unranked},
+    user_error::UserError,
+};
+
+use super::StartEventUndo;
+
+impl Unranked {
+    /// Serves as the link to the private function that returns the guard
+    fn guard_start_event_undo(&'_ self) -> anyhow::Result<MutexGuard<'_, StartEventUndo>> {
+        match self.start_event_undo.lock() {
+            Ok(guard) => Ok(guard),
+            Err(e) => anyhow::bail!("failed to lock mutex because '{e}"),
+        }
+    }
+
+    /// Saves a copy of the current ideas and scores (taken together) so the start event can be undone
+    #[instrument(skip(self))]
+    pub fn start_event_save_undo(&self) -> anyhow::Result<()> {
+        info!("START");
+        let ideas = self.guard_idea()?;
+        let scores = self.guard_scores()?;
+        let mut undo = self.guard_start_event_undo()?;
+        undo.set(
+            self.shared_config.clock.now()?,
+            ideas.clone(),
+            scores.clone(),
+        );
+        self.save(StartEventUndo::DATA_KEY, &*undo)?;
+        info!("END");
+        Ok(())
+    }
+
+    /// Restores the ideas and scores from before the last start event if it was less than
+    /// `grace` ago and returns when the snapshot was taken
+    #[instrument(skip(self))]
+    pub fn start_event_undo(&self, grace: Duration) -> anyhow::Result<UnixTimestamp> {
+        info!("START");
+        let mut ideas = self.guard_idea()?;
+        let mut scores = self.guard_scores()?;
+        let mut undo = self.guard_start_event_undo()?;
+        let Some(taken_at) = undo.taken_at() else {
+            bail!(UserError::new("There is no start event to undo"));
+        };
+        StartEventUndo::check_within_grace(taken_at, self.shared_config.clock.now()?, grace)?;
+        let (taken_at, old_ideas, old_scores) =
+            undo.take().expect("we just checked that a snapshot exists");
+        *ideas = old_ideas;
+        *scores = old_scores;
+        self.save_idea(&ideas)?;
+        self.save_scores(&scores)?;
+        self.save(StartEventUndo::DATA_KEY, &*undo)?;
+        info!("END");
+        Ok(taken_at)
+    }
+
+    /// Replaces the saved copy from before the last start event
+    pub fn start_event_undo_replace(&self, undo: StartEventUndo) -> anyhow::Result<PendingSave> {
+        let mut guard = self.guard_start_event_undo()?;
+        *guard = undo;
+        self.save(StartEventUndo::DATA_KEY, &*guard)
+    }
+}
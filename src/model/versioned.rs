@@ -0,0 +1,167 @@
+//! Stored values are wrapped in an envelope that records the version of their format.
+//!
+//! Each persisted type lists the functions needed to upgrade older documents so that when the
+//! format of a type changes, documents saved by older versions of the bot are brought forward at
+//! load time instead of failing to parse. Version 0 is the format from before the envelope existed
+//! (the bare serialized value)
+
+use anyhow::{Context as _, bail};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tracing::info;
+
+/// Converts a document from one version to the next
+pub type Upgrade = fn(Value) -> anyhow::Result<Value>;
+
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The function at index `i` upgrades a document from version `i` to version `i + 1`.
+    /// When the format changes add a function to the end of the list
+    const UPGRADES: &'static [Upgrade];
+
+    fn current_version() -> u32 {
+        Self::UPGRADES.len() as u32
+    }
+}
+
+/// The upgrade from the bare value to the first enveloped version (the data itself didn't change)
+pub fn from_unversioned(value: Value) -> anyhow::Result<Value> {
+    Ok(value)
+}
+
+#[derive(serde::Serialize)]
+struct EnvelopeRef<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(serde::Deserialize)]
+struct Envelope {
+    version: u32,
+    data: Value,
+}
+
+pub fn to_json<T: Versioned>(value: &T) -> anyhow::Result<String> {
+    serde_json::to_string(&EnvelopeRef {
+        version: T::current_version(),
+        data: value,
+    })
+    .context("failed to convert to json")
+}
+
+/// Same as [`to_json`] but formatted for people to read
+pub fn to_json_pretty<T: Versioned>(value: &T) -> anyhow::Result<String> {
+    serde_json::to_string_pretty(&EnvelopeRef {
+        version: T::current_version(),
+        data: value,
+    })
+    .context("failed to convert to json")
+}
+
+pub fn from_json<T: Versioned>(content: &str) -> anyhow::Result<T> {
+    let value: Value = serde_json::from_str(content).context("content is not valid json")?;
+    from_value(value)
+}
+
+/// Same as [`from_json`] for content that has already been parsed as json
+pub fn from_value<T: Versioned>(value: Value) -> anyhow::Result<T> {
+    let Envelope { version, mut data } = if is_envelope(&value) {
+        serde_json::from_value(value).context("failed to read envelope")?
+    } else {
+        Envelope {
+            version: 0,
+            data: value,
+        }
+    };
+    let current_version = T::current_version();
+    if version > current_version {
+        bail!(
+            "stored with format version {version} but this version of the bot only understands up to {current_version}"
+        );
+    }
+    for (from_version, upgrade) in T::UPGRADES.iter().enumerate().skip(version as usize) {
+        info!(
+            "Upgrading {} from version {from_version}",
+            std::any::type_name::<T>()
+        );
+        data = upgrade(data)
+            .with_context(|| format!("failed to upgrade from version {from_version}"))?;
+    }
+    serde_json::from_value(data).context("failed to convert upgraded data")
+}
+
+/// Envelopes have exactly the two fields `version` and `data` which none of the bare formats have
+fn is_envelope(value: &Value) -> bool {
+    match value.as_object() {
+        Some(map) => {
+            map.len() == 2
+                && map.get("version").is_some_and(Value::is_u64)
+                && map.contains_key("data")
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        heartbeat::{self, downtime::DowntimeHistory},
+        model::{
+            schedule::{ScheduledTasks, UnixTimestamp, history::ExecutionHistory},
+            unranked::{ideas::Ideas, scores::Scores, start_event_undo::StartEventUndo},
+        },
+    };
+
+    use super::*;
+
+    fn load_fixture<T: Versioned>(key: &str, version: u32) -> T {
+        let path = format!(
+            "{}/fixtures/{key}/v{version}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let content = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!("missing fixture {path:?} (add one for every version): {e}")
+        });
+        from_json(&content).unwrap_or_else(|e| panic!("failed to load {path:?}: {e:?}"))
+    }
+
+    /// Every past version must load and end up the same as the current version's fixture
+    fn check_all_versions<T: Versioned>(key: &str) {
+        let current = serde_json::to_value(load_fixture::<T>(key, T::current_version())).unwrap();
+        for version in 0..T::current_version() {
+            let upgraded = serde_json::to_value(load_fixture::<T>(key, version)).unwrap();
+            assert_eq!(upgraded, current, "{key} v{version} upgraded");
+        }
+    }
+
+    #[rstest]
+    #[case::ideas(Ideas::DATA_KEY, check_all_versions::<Ideas>)]
+    #[case::scores(Scores::DATA_KEY, check_all_versions::<Scores>)]
+    #[case::scheduled_tasks(ScheduledTasks::DATA_KEY, check_all_versions::<ScheduledTasks>)]
+    #[case::schedule_history(ExecutionHistory::DATA_KEY, check_all_versions::<ExecutionHistory>)]
+    #[case::heartbeat(heartbeat::KEY, check_all_versions::<UnixTimestamp>)]
+    #[case::downtime_history(DowntimeHistory::DATA_KEY, check_all_versions::<DowntimeHistory>)]
+    #[case::start_event_undo(StartEventUndo::DATA_KEY, check_all_versions::<StartEventUndo>)]
+    fn fixtures_load(#[case] key: &str, #[case] check: fn(&str)) {
+        check(key);
+    }
+
+    #[test]
+    fn round_trip_uses_envelope() {
+        let json = to_json(&UnixTimestamp::new(42)).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"version":{},"data":42}}"#,
+                UnixTimestamp::current_version()
+            )
+        );
+        assert_eq!(from_json::<UnixTimestamp>(&json).unwrap().0, 42);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        assert!(from_json::<UnixTimestamp>(r#"{"version":999,"data":42}"#).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Limits how often something is allowed to happen (eg. posting error reports to the status channel)
+
+use std::time::Duration;
+
+use crate::model::schedule::UnixTimestamp;
+
+/// Allows up to `max_per_window` events in each fixed window and counts the rest
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_per_window: usize,
+    window: Duration,
+    window_start: Option<UnixTimestamp>,
+    allowed_in_window: usize,
+    /// Events refused since the last one that was allowed
+    suppressed: usize,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_window: usize, window: Duration) -> Self {
+        Self {
+            max_per_window,
+            window,
+            window_start: None,
+            allowed_in_window: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns how many events were suppressed since the last allowed one if this one is allowed
+    /// and `None` if it is not
+    pub fn check(&mut self, now: UnixTimestamp) -> Option<usize> {
+        let is_new_window = self.window_start.is_none_or(|start| {
+            now.duration_since(start)
+                .is_none_or(|elapsed| elapsed >= self.window)
+        });
+        if is_new_window {
+            self.window_start = Some(now);
+            self.allowed_in_window = 0;
+        }
+        if self.allowed_in_window < self.max_per_window {
+            self.allowed_in_window += 1;
+            Some(std::mem::take(&mut self.suppressed))
+        } else {
+            self.suppressed += 1;
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_window_and_counts_suppressed() {
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let at = |seconds: i64| UnixTimestamp::new(1_893_456_000 + seconds);
+        assert_eq!(limiter.check(at(0)), Some(0));
+        assert_eq!(limiter.check(at(10)), Some(0));
+        assert_eq!(limiter.check(at(20)), None);
+        assert_eq!(limiter.check(at(59)), None);
+        assert_eq!(limiter.check(at(60)), Some(2));
+        assert_eq!(limiter.check(at(61)), Some(0));
+        assert_eq!(limiter.check(at(62)), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Stops the bot cleanly when asked to by the OS (Ctrl+C or SIGTERM) instead of just being killed
+
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use poise::serenity_prelude::{self as serenity, ShardManager};
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn};
+
+use crate::{Data, heartbeat};
+
+/// Waits in the background for a shutdown signal then shuts down the bot. Before this is called
+/// the process is still just killed by the signal. `background_tasks` (eg. the heartbeat) are
+/// stopped before anything is saved so they cannot write after the clean shutdown is recorded
+pub fn start_signal_handler(
+    data: Data,
+    ctx: serenity::Context,
+    shard_manager: Arc<ShardManager>,
+    background_tasks: Vec<JoinHandle<()>>,
+) {
+    tokio::spawn(async move {
+        if let Err(err) = wait_for_signal().await {
+            error!(?err, "failed to listen for shutdown signals");
+            return;
+        }
+        shutdown(&data, &ctx, background_tasks).await;
+        // Causes `client.start()` to return which ends the process
+        shard_manager.shutdown_all().await;
+    });
+}
+
+async fn wait_for_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate =
+            signal(SignalKind::terminate()).context("failed to listen for SIGTERM")?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.context("failed to listen for Ctrl+C")?,
+            _ = terminate.recv() => info!("Received SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .context("failed to listen for Ctrl+C")?;
+    Ok(())
+}
+
+/// Stops accepting commands and scheduled tasks then makes sure everything is saved before the
+/// bot disconnects
+#[instrument(skip_all)]
+async fn shutdown(data: &Data, ctx: &serenity::Context, background_tasks: Vec<JoinHandle<()>>) {
+    info!("START");
+    let shared_config = data.inner.shared_config;
+    shared_config.health.set_shutting_down();
+    if let Err(err) = data.schedule_abort_all() {
+        error!(?err, "failed to abort scheduled tasks");
+    }
+    stop_background_tasks(background_tasks).await;
+    if let Err(err) = heartbeat::record_clean_shutdown(shared_config) {
+        error!(?err, "failed to record clean shutdown");
+    }
+    if let Some(channel) = shared_config.channel_bot_status {
+        let msg = format!(
+            "{} is shutting down. Version: {}",
+            ctx.cache.current_user().name,
+            version::version!()
+        );
+        if let Err(err) = channel.say(ctx, msg).await {
+            error!(?err, "failed to send shutdown notification");
+        }
+    } else {
+        warn!("Not sending shutdown notification because channel_bot_status not set");
+    }
+    if let Err(err) = shared_config.flush_kv().await {
+        error!(?err, "failed to write pending saves before shutting down");
+    }
+    info!("END");
+}
+
+/// Aborts the tasks and waits until each has actually stopped (an abort only takes effect the next
+/// time the task yields)
+pub(crate) async fn stop_background_tasks(tasks: Vec<JoinHandle<()>>) {
+    for task in tasks {
+        task.abort();
+        match task.await {
+            Ok(()) => {}
+            Err(err) if err.is_cancelled() => {}
+            Err(err) => error!(?err, "background task failed before shutdown"),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Errors caused by how a command was used (eg. a time that could not be read or an ID that does
+//! not exist) rather than by a problem with the bot. They are shown to the user as is and are not
+//! reported to the bot status channel
+
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct UserError(String);
+
+impl UserError {
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+
+    /// Returns true iff `error` is a user error or was caused by one
+    pub fn is_cause_of(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| cause.is::<Self>())
+    }
+}
+
+impl Display for UserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UserError {}
+
+/// Marks the error as being caused by the user's input (eg. parsing what they typed)
+pub trait OrUserError<T> {
+    fn or_user_error(self) -> Result<T, UserError>;
+}
+
+impl<T, E: Display> OrUserError<T> for Result<T, E> {
+    fn or_user_error(self) -> Result<T, UserError> {
+        self.map_err(|e| UserError(format!("{e:#}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context as _, bail};
+
+    use super::*;
+
+    #[test]
+    fn found_through_context() {
+        let result: anyhow::Result<()> = Err(UserError::new("bad input")).context("while editing");
+        assert!(UserError::is_cause_of(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn other_errors_are_not_user_errors() {
+        let result: anyhow::Result<()> = (|| bail!("disk full"))();
+        assert!(!UserError::is_cause_of(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn marks_parse_errors() {
+        let error: anyhow::Error = "x".parse::<u8>().or_user_error().unwrap_err().into();
+        assert!(UserError::is_cause_of(&error));
+        assert_eq!(error.to_string(), "invalid digit found in string");
+    }
+}